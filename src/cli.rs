@@ -1,3 +1,4 @@
+use crate::{export::ExportFormat, report::ReportFormat};
 use chrono::{offset::Utc, DateTime};
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -24,4 +25,71 @@ pub struct Opt {
         help = "contribution end time in rfc3339 format, ex: 2021-08-01T00:00:00-00:00"
     )]
     pub end: Option<DateTime<Utc>>,
+    #[structopt(
+        long,
+        help = "format to export the flattened contribution records in, one of: csv, json",
+        default_value("csv")
+    )]
+    pub format: ExportFormat,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "write a flat export of every contribution to this file, one row per contribution"
+    )]
+    pub output: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "GitHub Enterprise Server API root to collect from instead of github.com, ex: https://ghe.example.com/api/v3, overrides the config file's github_url"
+    )]
+    pub github_url: Option<String>,
+    #[structopt(
+        long,
+        help = "maximum number of repos, and PR reviews within a repo, to fetch concurrently",
+        default_value("10")
+    )]
+    pub concurrency: usize,
+    #[structopt(
+        long,
+        help = "GitHub App ID to authenticate as instead of GITHUB_TOKEN, overrides the config file's github_app.app_id; requires --app-installation-id and --app-private-key"
+    )]
+    pub app_id: Option<u64>,
+    #[structopt(
+        long,
+        help = "installation ID of the GitHub App to collect on behalf of, overrides the config file's github_app.installation_id"
+    )]
+    pub app_installation_id: Option<u64>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "path to the GitHub App's PEM-encoded private key, overrides the config file's github_app.private_key_path"
+    )]
+    pub app_private_key: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "path to a SQLite database used to cache enriched users and fetched contributions across runs, overrides the config file's cache.path"
+    )]
+    pub cache_db: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "hours a cached enriched user is served without re-fetching, overrides the config file's cache.ttl_hours"
+    )]
+    pub cache_ttl_hours: Option<i64>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "directory to cache raw API response bodies in by request URL, sending their stored ETag on later runs so unchanged pages come back as a free 304 instead of counting against the rate limit; overrides the config file's response_cache_dir"
+    )]
+    pub response_cache_dir: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "format to print the per-user/per-repo contribution report in, one of: table, json, csv",
+        default_value("table")
+    )]
+    pub report_format: ReportFormat,
+    #[structopt(
+        long,
+        help = "fetch commits, issues, and reviews via batched GraphQL queries instead of one REST request (and implicit pagination) per contribution type; also enabled by the config file's graphql key"
+    )]
+    pub graphql: bool,
 }
@@ -1,5 +1,6 @@
 use crate::models;
 use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize)]
 /// Config file for contribution collector
@@ -19,6 +20,57 @@ pub struct Config {
     pub user_overrides: Vec<UserOverride>,
     #[serde(default)]
     pub users_exclude: Vec<String>,
+    /// drop contributions from bot accounts (any login GitHub suffixes with
+    /// `[bot]`, e.g. `dependabot[bot]`) instead of counting their automated
+    /// commits/PRs as a contributor's own work.
+    #[serde(default)]
+    pub skip_bots: bool,
+    /// API root of a GitHub Enterprise Server instance to collect from
+    /// instead of github.com, e.g. `https://ghe.example.com/api/v3`.
+    #[serde(default)]
+    pub github_url: Option<String>,
+    /// GitHub App credentials to authenticate with instead of `GITHUB_TOKEN`,
+    /// for the much higher installation rate limits an org-wide audit needs.
+    #[serde(default)]
+    pub github_app: Option<GithubApp>,
+    /// SQLite cache of enriched users and fetched contributions, so repeated
+    /// runs over overlapping windows don't re-enrich users within the TTL,
+    /// and runs without a start time only fetch issues and commits newer
+    /// than the cached ones.
+    #[serde(default)]
+    pub cache: Option<Cache>,
+    /// Directory to cache raw API response bodies in by request URL, so
+    /// unchanged pages come back as a `304 Not Modified` on a later run
+    /// instead of counting against the rate limit.
+    #[serde(default)]
+    pub response_cache_dir: Option<PathBuf>,
+    /// Fetch commits, issues, and reviews via batched GraphQL queries instead
+    /// of one REST request (and implicit pagination) per contribution type.
+    #[serde(default)]
+    pub graphql: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubApp {
+    pub app_id: u64,
+    pub installation_id: u64,
+    /// path to the App's PEM-encoded private key on disk.
+    pub private_key_path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Cache {
+    /// path to the SQLite database file.
+    pub path: PathBuf,
+    /// hours a cached enriched user is served before being re-fetched.
+    #[serde(default = "Cache::default_ttl_hours")]
+    pub ttl_hours: i64,
+}
+
+impl Cache {
+    fn default_ttl_hours() -> i64 {
+        24
+    }
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -29,9 +81,21 @@ pub struct Repo {
     pub companies_exclude: Vec<String>,
 }
 
+/// An organization to exclude contributors from by real GitHub org/team
+/// membership rather than free-text profile matching alone.
 #[derive(Debug, Deserialize)]
 pub struct Org {
+    /// the GitHub org login to check membership against.
     pub name: String,
+    /// slugs of teams within `name` whose members count as affiliated even
+    /// if they aren't (or can't be confirmed as) direct org members, e.g. an
+    /// outside collaborator added to a single team.
+    #[serde(default)]
+    pub teams: Vec<String>,
+    /// company/email text to match against a contributor's profile when
+    /// neither `name`'s nor `teams`' membership lists are visible to us
+    /// (e.g. a private org).
+    #[serde(default)]
     pub companies_exclude: Vec<String>,
 }
 
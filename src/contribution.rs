@@ -1,8 +1,9 @@
-use crate::models::{commit::EnrichedCommit, Repo};
+use crate::models::{commit::EnrichedCommit, issue_event::IssueEvent, Repo};
 use chrono::{offset::Utc, DateTime};
 use octocrab::models::{issues::Issue, pulls::Review, User};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Contribution {
     pub repo: Repo,
     pub contribution: GithubContribution,
@@ -22,11 +23,12 @@ impl Contribution {
 }
 
 /// GitHub Contribution as defined in the [GitHub documentation](https://docs.github.com/en/github/setting-up-and-managing-your-github-profile/managing-contribution-graphs-on-your-profile/viewing-contributions-on-your-profile#what-counts-as-a-contribution).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum GithubContribution {
     Commit(EnrichedCommit),
     Issue(Issue),
     Review(Review),
+    IssueEvent(IssueEvent),
 }
 
 impl Contribution {
@@ -36,6 +38,7 @@ impl Contribution {
             GithubContribution::Commit(commit) => Some(commit.commit.author.date),
             GithubContribution::Issue(issue) => Some(issue.created_at),
             GithubContribution::Review(review) => review.submitted_at,
+            GithubContribution::IssueEvent(event) => Some(event.created_at),
         }
     }
 
@@ -44,6 +47,55 @@ impl Contribution {
             GithubContribution::Commit(commit) => commit.inner.author.as_ref(),
             GithubContribution::Issue(issue) => Some(&issue.user),
             GithubContribution::Review(review) => Some(&review.user),
+            GithubContribution::IssueEvent(event) => Some(&event.actor),
+        }
+    }
+
+    /// Canonical identity to aggregate this contribution's contributor by:
+    /// the immutable numeric GitHub user id when there's a linked account,
+    /// or (since commits with none would otherwise all collapse into one
+    /// shared bucket) the commit author's email when there isn't.
+    pub fn contributor_key(&self) -> ContributorKey {
+        if let Some(user) = self.user() {
+            return ContributorKey::Id(user.id.0);
+        }
+
+        match &self.contribution {
+            GithubContribution::Commit(commit) => {
+                ContributorKey::Email(commit.commit.author.email.to_lowercase())
+            }
+            _ => ContributorKey::Unknown,
+        }
+    }
+}
+
+/// See [`Contribution::contributor_key`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ContributorKey {
+    Id(u64),
+    Email(String),
+    Unknown,
+}
+
+impl GithubContribution {
+    /// Stable string name for this variant, used as the `type` column when
+    /// exporting or caching contributions.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GithubContribution::Commit(_) => "commit",
+            GithubContribution::Issue(_) => "issue",
+            GithubContribution::Review(_) => "review",
+            GithubContribution::IssueEvent(_) => "issue_event",
+        }
+    }
+
+    /// Stable id within `kind()`, used as the cache/export row key.
+    pub fn id(&self) -> String {
+        match self {
+            GithubContribution::Commit(commit) => commit.inner.sha.clone(),
+            GithubContribution::Issue(issue) => issue.id.to_string(),
+            GithubContribution::Review(review) => review.id.to_string(),
+            GithubContribution::IssueEvent(event) => event.id.to_string(),
         }
     }
 }
@@ -65,3 +117,9 @@ impl From<Review> for GithubContribution {
         GithubContribution::Review(review)
     }
 }
+
+impl From<IssueEvent> for GithubContribution {
+    fn from(event: IssueEvent) -> GithubContribution {
+        GithubContribution::IssueEvent(event)
+    }
+}
@@ -0,0 +1,99 @@
+//! Flatten `Output`s into a tabular record so callers can hand the results
+//! off to spreadsheets and other downstream tooling instead of only
+//! debug-printing them.
+use crate::{github_contribution_collector::Output, Contribution};
+use chrono::{offset::Utc, DateTime};
+use serde::Serialize;
+use std::io::Write;
+
+/// One contribution, flattened alongside the contributor it was attributed
+/// to, suitable for serializing to CSV or newline-delimited JSON.
+#[derive(Debug, Serialize)]
+pub struct OutputRec {
+    pub user_login: Option<String>,
+    pub user_id: Option<String>,
+    pub company: Option<String>,
+    pub email: Option<String>,
+    pub membership: bool,
+    pub repo_org: String,
+    pub repo_name: String,
+    pub contribution_type: &'static str,
+    pub contribution_id: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<&Contribution> for OutputRec {
+    fn from(contribution: &Contribution) -> Self {
+        OutputRec {
+            user_login: None,
+            user_id: None,
+            company: None,
+            email: None,
+            membership: false,
+            repo_org: contribution.repo.org.clone(),
+            repo_name: contribution.repo.name.clone(),
+            contribution_type: contribution.contribution.kind(),
+            contribution_id: contribution.contribution.id(),
+            created_at: contribution.created_at(),
+        }
+    }
+}
+
+/// Flatten a set of `Output`s into one record per contribution, filling in
+/// the contributor's details (if any) on every row.
+pub fn flatten(outputs: &[Output]) -> Vec<OutputRec> {
+    outputs
+        .iter()
+        .flat_map(|output| {
+            output.contributions.iter().map(move |contribution| {
+                let mut record = OutputRec::from(contribution);
+
+                if let Some(user) = output.user.as_ref() {
+                    record.user_login = Some(user.inner.login.clone());
+                    record.user_id = Some(user.inner.id.to_string());
+                    record.company = user.company.clone();
+                    record.email = user.email.clone();
+                }
+                record.membership = output.membership;
+
+                record
+            })
+        })
+        .collect()
+}
+
+/// Export format for the flattened records, selectable via `Opt::format`.
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!("unknown export format `{}`, expected csv or json", other)),
+        }
+    }
+}
+
+pub fn write_csv(records: &[OutputRec], writer: impl Write) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for record in records {
+        writer.serialize(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_json(records: &[OutputRec], mut writer: impl Write) -> std::io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
@@ -1,12 +1,20 @@
 use crate::{
-    config,
-    models::{self, commit::EnrichedCommit, EnrichedUser},
+    config, contribution,
+    models::{self, commit::EnrichedCommit, issue_event::IssueEvent, EnrichedUser},
     Contribution,
 };
+
+mod auth;
+mod backoff;
+mod cache;
+mod graphql;
+mod store;
+pub use auth::Auth;
+
 use async_recursion::async_recursion;
 use async_stream::try_stream;
-use chrono::{offset::TimeZone, DateTime};
-use futures::Stream;
+use chrono::{offset::TimeZone, offset::Utc, DateTime};
+use futures::{stream, Stream};
 use octocrab::{
     models::{
         issues::Issue,
@@ -20,9 +28,13 @@ use regex::Regex;
 use serde::de::DeserializeOwned;
 use std::{collections::HashMap, fmt, marker::Send, sync::Arc};
 use tokio_stream::StreamExt;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 const MAX_TRIES: usize = 5;
+const DEFAULT_BASE_URL: &str = "https://api.github.com";
+/// How many in-flight requests `with_concurrency` allows by default when a
+/// caller doesn't tune it against their own rate-limit budget.
+const DEFAULT_CONCURRENCY: usize = 10;
 
 /// Common Parameters for the GitHub API
 #[derive(Clone, Debug)]
@@ -65,7 +77,22 @@ struct ExcludeRegex {
     pub email: Regex,
 }
 
-#[derive(Debug)]
+/// Compile each configured company name into a `company`/`email`
+/// regex pair, shared by both the per-repo and per-org exclude lists.
+fn compile_excludes(companies_exclude: &[String]) -> Vec<ExcludeRegex> {
+    companies_exclude
+        .iter()
+        .map(|company| ExcludeRegex {
+            company: Regex::new(format!(r#"(?i){}"#, regex::escape(company.as_ref())).as_str()).unwrap(),
+            email: Regex::new(
+                format!(r#"@(\w\.)*(?i){}(?-i)\."#, regex::escape(company.as_ref())).as_str(),
+            )
+            .unwrap(),
+        })
+        .collect()
+}
+
+#[derive(Debug, serde::Serialize)]
 pub struct Output {
     pub user: Option<EnrichedUser>,
     pub membership: bool,
@@ -82,28 +109,50 @@ impl From<&config::Repo> for RepoRegex {
     fn from(value: &config::Repo) -> Self {
         RepoRegex {
             repo: value.repo.clone(),
-            companies_exclude: value
-                .companies_exclude
-                .iter()
-                .map(|company| ExcludeRegex {
-                    company: Regex::new(
-                        format!(r#"(?i){}"#, regex::escape(company.as_ref())).as_str(),
-                    )
-                    .unwrap(),
-                    email: Regex::new(
-                        format!(r#"@(\w\.)*(?i){}(?-i)\."#, regex::escape(company.as_ref()))
-                            .as_str(),
-                    )
-                    .unwrap(),
-                })
-                .collect(),
+            companies_exclude: compile_excludes(&value.companies_exclude),
+        }
+    }
+}
+
+/// A configured excluded organization: contributors confirmed as members of
+/// `name`, or of any of `teams` within it, via the GitHub membership API are
+/// treated as company-affiliated outright; `companies_exclude` is only
+/// consulted when neither API has anything to say (e.g. the token can't see
+/// a private org's members), matching against the contributor's free-text
+/// profile company/email instead.
+#[derive(Debug, Clone)]
+struct OrgRegex {
+    name: String,
+    teams: Vec<String>,
+    companies_exclude: Vec<ExcludeRegex>,
+}
+
+impl From<&config::Org> for OrgRegex {
+    fn from(value: &config::Org) -> Self {
+        OrgRegex {
+            name: value.name.clone(),
+            teams: value.teams.clone(),
+            companies_exclude: compile_excludes(&value.companies_exclude),
         }
     }
 }
 
 /// Client for getting different types of GitHub contributions
 pub struct GithubContributionCollector {
-    client: Arc<octocrab::Octocrab>,
+    /// When set, fetch reviews through a single batched GraphQL query per
+    /// repo instead of one REST `list_reviews` call per pull request.
+    graphql: bool,
+    /// Our own HTTP context for requests that need more control than
+    /// octocrab's helpers give us -- conditional caching and rate-limit
+    /// aware backoff on paginated fetches.
+    http: Arc<cache::HttpContext>,
+    /// Upper bound on in-flight requests when fetching multiple pull
+    /// requests' reviews or multiple repos' contributions concurrently.
+    concurrency: usize,
+    /// When set, persists enriched users and fetched contributions to a
+    /// SQLite database so overlapping runs only enrich/fetch what's
+    /// changed since the last one.
+    store: Option<Arc<store::Store>>,
 }
 
 impl fmt::Debug for GithubContributionCollector {
@@ -113,28 +162,142 @@ impl fmt::Debug for GithubContributionCollector {
 }
 
 impl GithubContributionCollector {
-    pub fn new(token: Option<impl Into<String>>) -> Result<Self, octocrab::Error> {
-        let mut client = octocrab::OctocrabBuilder::new();
-        if let Some(token) = token {
-            client = client.personal_token(token.into());
-        }
+    /// Build a collector authenticating with `auth`. For `Auth::App`, this
+    /// mints the initial GitHub App installation access token up front, so
+    /// it can fail the same way a bad personal access token would.
+    pub async fn new(auth: Auth) -> Result<Self, octocrab::Error> {
+        let http = reqwest::Client::new();
+        let token_source = Arc::new(auth::TokenSource::new(
+            auth,
+            http.clone(),
+            DEFAULT_BASE_URL.to_string(),
+        ));
+        // Fail fast the same way a bad personal access token would, without
+        // keeping this token around -- `client()` asks the token source for
+        // the current one on every call.
+        token_source.token().await?;
 
         Ok(Self {
-            client: Arc::new(client.build()?),
+            graphql: false,
+            http: Arc::new(cache::HttpContext {
+                http,
+                token: token_source,
+                cache: None,
+                base_url: DEFAULT_BASE_URL.to_string(),
+            }),
+            concurrency: DEFAULT_CONCURRENCY,
+            store: None,
         })
     }
 
+    /// Build an `octocrab::Octocrab` client authenticated with whatever
+    /// token is current right now. Installation tokens expire in about an
+    /// hour, so unlike a field built once at construction time, this always
+    /// goes through `self.http.token`, which refreshes the token itself
+    /// (with a couple of minutes' skew) before it actually expires.
+    async fn client(&self) -> Result<octocrab::Octocrab, octocrab::Error> {
+        let token = self.http.token.token().await?;
+        octocrab::OctocrabBuilder::new()
+            .base_uri(&self.http.base_url)?
+            .personal_token(token)
+            .build()
+    }
+
+    /// Cap how many requests (PR reviews within a repo, or whole repos'
+    /// worth of contributions) are fetched concurrently. Tune this down
+    /// against a tight rate-limit budget, or up on a high-limit GitHub App
+    /// token.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// The concurrency limit in effect, for callers fanning out their own
+    /// per-repo work (e.g. across multiple repos) who want to stay under
+    /// the same budget.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Fetch commits, issues, and reviews via cursor-paginated GraphQL
+    /// queries instead of one REST request (and implicit pagination) per
+    /// contribution type. Opt in on busy repos where the REST request
+    /// volume burns through the rate limit.
+    pub fn with_graphql(mut self, graphql: bool) -> Self {
+        self.graphql = graphql;
+        self
+    }
+
+    /// Cache API responses on disk under `dir`, keyed by request URL, and
+    /// send their stored `ETag` as `If-None-Match` on subsequent runs so
+    /// unchanged pages come back as a `304 Not Modified` instead of
+    /// counting against the rate limit.
+    pub fn with_cache(mut self, dir: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        self.http = Arc::new(cache::HttpContext {
+            http: self.http.http.clone(),
+            token: self.http.token.clone(),
+            cache: Some(cache::ResponseCache::new(dir)?),
+            base_url: self.http.base_url.clone(),
+        });
+        Ok(self)
+    }
+
+    /// Persist enriched users and fetched contributions to a SQLite
+    /// database at `path`, so overlapping runs only re-enrich users whose
+    /// cached record is older than `ttl`, and fetched contributions are
+    /// merged with the cached ones. Only REST issue and commit requests are
+    /// narrowed by the cache, and only when the caller gives no `since` (see
+    /// `params_since_cache`); reviews, issue events, and everything fetched
+    /// in GraphQL mode are re-fetched in full on every run.
+    pub fn with_sqlite_cache(
+        mut self,
+        path: impl AsRef<std::path::Path>,
+        ttl: chrono::Duration,
+    ) -> rusqlite::Result<Self> {
+        self.store = Some(Arc::new(store::Store::open(path, ttl)?));
+        Ok(self)
+    }
+
+    /// Point the collector at a GitHub Enterprise Server instance's API root
+    /// (e.g. `https://ghe.example.com/api/v3`) instead of github.com, for
+    /// both octocrab's own requests and our hand-rolled ones. Mints a fresh
+    /// token against the new root (GitHub App installation tokens are
+    /// exchanged against the API root they'll be used on).
+    pub async fn with_github_url(mut self, github_url: impl AsRef<str>) -> Result<Self, octocrab::Error> {
+        let github_url = github_url.as_ref().trim_end_matches('/');
+
+        let token_source = Arc::new(auth::TokenSource::new(
+            self.http.token.auth().clone(),
+            self.http.http.clone(),
+            github_url.to_string(),
+        ));
+        // Fail fast against the new root, same as `new()`.
+        token_source.token().await?;
+
+        self.http = Arc::new(cache::HttpContext {
+            http: self.http.http.clone(),
+            token: token_source,
+            cache: self.http.cache.clone(),
+            base_url: github_url.to_string(),
+        });
+
+        Ok(self)
+    }
+
     /// Given an Iterator of `Contribution`s, generate a `Vec<Output>`.
     /// ## Notes
-    /// `company_orgs` requires access from the GitHub Token provided.
+    /// `company_orgs` and `orgs_exclude` both require access from the
+    /// GitHub Token provided.
     #[instrument(skip(self, contributions))]
     pub async fn process_contributions<TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
         &self,
         contributions: impl Iterator<Item = Contribution> + fmt::Debug,
         company_orgs: impl Iterator<Item = impl AsRef<str>> + Clone + fmt::Debug,
+        orgs_exclude: impl Iterator<Item = &config::Org> + Clone + fmt::Debug,
         repos: impl Iterator<Item = &config::Repo> + Clone + fmt::Debug,
         user_overrides: impl Iterator<Item = config::UserOverride> + fmt::Debug,
         users_exclude: impl Iterator<Item = impl AsRef<str>> + Clone + fmt::Debug,
+        skip_bots: bool,
         params: Params<TzA, TzB>,
     ) -> Result<Vec<Output>, octocrab::Error>
     where
@@ -142,17 +305,22 @@ impl GithubContributionCollector {
         TzB::Offset: fmt::Display,
     {
         let repos_re = repos.map(|repo| RepoRegex::from(repo));
+        let orgs_re = orgs_exclude.map(|org| OrgRegex::from(org));
         let user_overrides_map: HashMap<String, config::UserOverride> = user_overrides
             .map(|user_override| (user_override.login.clone(), user_override))
             .collect();
 
         let collection = output_stream(
-            self.client.clone(),
+            Arc::new(self.client().await?),
+            self.http.clone(),
+            self.store.clone(),
             contributions,
             company_orgs,
+            orgs_re,
             repos_re,
             user_overrides_map,
             users_exclude,
+            skip_bots,
             params,
         )
         .await
@@ -174,28 +342,149 @@ impl GithubContributionCollector {
         TzB::Offset: fmt::Display,
     {
         info!("Fetching contributions: {}", repo);
-        let (issues, reviews, commits) = tokio::join!(
-            self.issues(repo, params),
+
+        if self.graphql {
+            let client = self.client().await?;
+
+            // Issues, commits, and reviews all come back from one paginated
+            // GraphQL query shape; only issue events still need their own
+            // REST call, since GitHub's GraphQL schema has no timeline
+            // equivalent for them.
+            let (fetched, events) = tokio::join!(
+                graphql::contributions(&client, repo, params),
+                self.events(repo),
+            );
+
+            let mut by_kind: HashMap<&'static str, Vec<Contribution>> = HashMap::new();
+            for contribution in fetched? {
+                by_kind.entry(contribution.contribution.kind()).or_default().push(contribution);
+            }
+            by_kind.entry("issue_event").or_default().extend(
+                events?
+                    .into_iter()
+                    .map(|event| Contribution::new(&repo.org, &repo.name, event.into())),
+            );
+
+            return Ok(by_kind
+                .into_iter()
+                .flat_map(|(kind, fresh)| self.cache_contributions(repo, kind, fresh))
+                .collect());
+        }
+
+        // Issues and commits support a `since` filter: narrow the request to
+        // whatever's changed since the later of what the caller asked for
+        // and what the cache already has, instead of re-pulling everything.
+        let issues_params = self.params_since_cache(repo, "issue", params);
+        let commits_params = self.params_since_cache(repo, "commit", params);
+
+        let (issues, reviews, commits, events) = tokio::join!(
+            self.issues(repo, &issues_params),
             self.reviews(repo),
-            self.commits(repo, params),
+            self.commits(repo, &commits_params),
+            self.events(repo),
+        );
+
+        let issues = self.cache_contributions(
+            repo,
+            "issue",
+            issues?
+                .into_iter()
+                .map(|issue| Contribution::new(&repo.org, &repo.name, issue.into()))
+                .collect(),
+        );
+        let reviews = self.cache_contributions(
+            repo,
+            "review",
+            reviews?
+                .into_iter()
+                .map(|review| Contribution::new(&repo.org, &repo.name, review.into()))
+                .collect(),
+        );
+        let commits = self.cache_contributions(
+            repo,
+            "commit",
+            commits?
+                .into_iter()
+                .map(|commit| Contribution::new(&repo.org, &repo.name, commit.into()))
+                .collect(),
+        );
+        let events = self.cache_contributions(
+            repo,
+            "issue_event",
+            events?
+                .into_iter()
+                .map(|event| Contribution::new(&repo.org, &repo.name, event.into()))
+                .collect(),
         );
 
-        let contributions = issues?
+        Ok(issues
             .into_iter()
-            .map(|issue| Contribution::new(&repo.org, &repo.name, issue.into()))
-            .chain(
-                reviews?
-                    .into_iter()
-                    .map(|review| Contribution::new(&repo.org, &repo.name, review.into())),
-            )
-            .chain(
-                commits?
-                    .into_iter()
-                    .map(|commit| Contribution::new(&repo.org, &repo.name, commit.into())),
-            )
+            .chain(reviews)
+            .chain(commits)
+            .chain(events)
+            .collect())
+    }
+
+    /// The `since` to fetch `kind` for `repo` with: the caller's own `since`
+    /// when one is given, otherwise the most recent contribution of `kind`
+    /// already cached for `repo`, so a repeat run without a start time only
+    /// asks the API for what's new. A caller-given `since` is never moved
+    /// by the cache: if it's earlier than the cache's high-water mark (e.g.
+    /// a first run with `--start 2024-06-01` followed by a rerun with
+    /// `--start 2023-01-01`), the cache can't prove it already covers that
+    /// earlier range, so it isn't used to skip fetching it.
+    fn params_since_cache<TzA: TimeZone, TzB: TimeZone>(
+        &self,
+        repo: &models::Repo,
+        kind: &str,
+        params: &Params<TzA, TzB>,
+    ) -> Params<Utc, TzB>
+    where
+        TzA::Offset: fmt::Display,
+        TzB::Offset: fmt::Display,
+    {
+        let cached_since = self.store.as_ref().and_then(|store| store.last_updated(repo, kind));
+        let requested_since = params.since.as_ref().map(|since| since.with_timezone(&Utc));
+
+        let since = match (cached_since, requested_since) {
+            (Some(cached), Some(requested)) if requested < cached => Some(requested),
+            (cached, requested) => cached.into_iter().chain(requested).max(),
+        };
+
+        Params {
+            since,
+            until: params.until.clone(),
+        }
+    }
+
+    /// Write freshly-fetched contributions of `kind` back to the cache (if
+    /// one is configured), then return them merged with whatever was
+    /// already cached -- deduping by id so a re-fetched item's latest copy
+    /// wins.
+    fn cache_contributions(
+        &self,
+        repo: &models::Repo,
+        kind: &str,
+        fresh: Vec<Contribution>,
+    ) -> Vec<Contribution> {
+        let Some(store) = self.store.as_ref() else {
+            return fresh;
+        };
+
+        if let Err(err) = store.put_contributions(repo, kind, &fresh) {
+            warn!(%repo, %kind, %err, "failed to write contributions to cache");
+        }
+
+        let mut by_id: HashMap<String, Contribution> = store
+            .contributions(repo, kind)
+            .into_iter()
+            .map(|contribution| (contribution.contribution.id(), contribution))
             .collect();
+        for contribution in fresh {
+            by_id.insert(contribution.contribution.id(), contribution);
+        }
 
-        Ok(contributions)
+        by_id.into_values().collect()
     }
 
     /// Collect all contributions from commits on the default branch associated with this repo.
@@ -209,10 +498,10 @@ impl GithubContributionCollector {
         TzA::Offset: fmt::Display,
         TzB::Offset: fmt::Display,
     {
-        match commit_page(self.client.clone(), repo, params).await? {
+        match commit_page(&self.http, repo, params).await? {
             Some(page) => {
                 info!(pages = ?page.number_of_pages(), "type" = "commits");
-                Ok(process_pages(&self.client, page).await?)
+                Ok(process_pages(&self.http, page).await?)
             }
             None => Ok(vec![]),
         }
@@ -229,11 +518,25 @@ impl GithubContributionCollector {
         TzA::Offset: fmt::Display,
         TzB::Offset: fmt::Display,
     {
-        match issues_page(self.client.clone(), repo, params).await? {
+        match issues_page(&self.http, repo, params).await? {
             Some(page) => {
                 info!(pages = ?page.number_of_pages(), "type" = "issues");
 
-                Ok(process_pages(&self.client, page).await?)
+                Ok(process_pages(&self.http, page).await?)
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Collect all issue timeline events (labeling, assigning, closing,
+    /// review requests, etc.) associated with this repo.
+    #[instrument(skip(self))]
+    pub async fn events(&self, repo: &models::Repo) -> Result<Vec<IssueEvent>, octocrab::Error> {
+        match issue_events_page(&self.http, repo).await? {
+            Some(page) => {
+                info!(pages = ?page.number_of_pages(), "type" = "issue_events");
+
+                Ok(process_pages(&self.http, page).await?)
             }
             None => Ok(vec![]),
         }
@@ -242,19 +545,30 @@ impl GithubContributionCollector {
     /// Collect all contributions reviews associated with this repo.
     #[instrument(skip(self))]
     pub async fn reviews(&self, repo: &models::Repo) -> Result<Vec<Review>, octocrab::Error> {
-        match pull_request_page(self.client.clone(), repo).await? {
+        let client = Arc::new(self.client().await?);
+
+        if self.graphql {
+            return graphql::reviews(&client, repo).await;
+        }
+
+        match pull_request_page(client.clone(), repo).await? {
             Some(page) => {
                 info!(pages = ?page.number_of_pages(), "type" = "pull_requests");
-                let pull_requests = process_pages(&self.client, page).await?;
+                let pull_requests = process_pages(&self.http, page).await?;
 
-                let reviews =
-                    review_stream(self.client.clone(), pull_requests.into_iter(), repo.clone())
-                        .await
-                        .collect::<Result<Vec<Vec<Review>>, octocrab::Error>>()
-                        .await?
-                        .into_iter()
-                        .flatten()
-                        .collect();
+                let reviews = review_stream(
+                    client.clone(),
+                    self.http.clone(),
+                    pull_requests.into_iter(),
+                    repo.clone(),
+                    self.concurrency,
+                )
+                .await
+                .collect::<Result<Vec<Vec<Review>>, octocrab::Error>>()
+                .await?
+                .into_iter()
+                .flatten()
+                .collect();
 
                 Ok(reviews)
             }
@@ -263,45 +577,129 @@ impl GithubContributionCollector {
     }
 }
 
+/// Turn a non-2xx GitHub API response body into an `octocrab::Error::GitHub`,
+/// falling back to a `Serde` error if the body isn't the usual error shape.
+fn github_error(body: &str) -> octocrab::Error {
+    match serde_json::from_str(body) {
+        Ok(source) => octocrab::Error::GitHub {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        },
+        Err(source) => octocrab::Error::Serde {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        },
+    }
+}
+
+/// Fetch the next page, retrying transient failures with a delay informed by
+/// GitHub's rate-limit and abuse-detection headers (falling back to
+/// exponential backoff with full jitter) instead of hammering the API
+/// immediately. 404/422 are treated as permanent and surfaced right away.
 #[async_recursion]
 async fn retry_get_page<T: 'async_recursion + DeserializeOwned + fmt::Debug + Send>(
-    client: &octocrab::Octocrab,
+    http_ctx: &'async_recursion cache::HttpContext,
     url: &Option<url::Url>,
-    tries_left: usize,
+    attempt: u32,
 ) -> octocrab::Result<Option<Page<T>>> {
-    let result = client.get_page::<T>(url).await;
+    let url = match url {
+        Some(url) => url,
+        None => return Ok(None),
+    };
 
-    if result.is_err() && tries_left >= 2 {
-        retry_get_page(client, url, tries_left - 1).await
-    } else {
-        result
+    let response = cache::fetch(http_ctx, url.as_str()).await?;
+
+    if response.status.is_success() || response.status == reqwest::StatusCode::NOT_MODIFIED {
+        let mut page: Page<T> =
+            serde_json::from_str(&response.body).map_err(|source| octocrab::Error::Serde {
+                source,
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+
+        // GitHub's list endpoints return a bare JSON array -- pagination is
+        // only communicated via the `Link` response header, which octocrab
+        // itself would parse for us through `Octocrab::get_page`. Since we
+        // fetch by hand here (to get `cache::fetch`'s ETag/backoff
+        // handling), we have to parse it ourselves or `page.next` stays
+        // `None` forever and every list silently truncates to one page.
+        let (next, prev) = cache::parse_link_header(&response.headers);
+        page.next = next;
+        page.prev = prev;
+
+        return Ok(Some(page));
     }
+
+    if backoff::is_permanent(response.status) || attempt + 1 >= MAX_TRIES as u32 {
+        return Err(github_error(&response.body));
+    }
+
+    tokio::time::sleep(backoff::delay_for(&response.headers, attempt)).await;
+
+    retry_get_page(http_ctx, &Some(url.clone()), attempt + 1).await
 }
 
-/// Given an `Iterator` of Contributions, return a HashMap where the key is the User and the value is
-/// a `Vec` of those contributions.
+/// Retry a request made through octocrab's own client (rather than
+/// `cache::fetch`, which `retry_get_page` wraps) up to `MAX_TRIES` times when
+/// it looks like a rate limit or a transport failure (see
+/// `backoff::is_retryable_error`), backing off the same exponential-with-jitter
+/// schedule `retry_get_page` falls back to when no rate-limit headers are
+/// available -- octocrab doesn't hand us the response headers here, only the
+/// parsed error body.
+pub(super) async fn retry_request<T, F, Fut>(mut request: F) -> octocrab::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = octocrab::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt + 1 < MAX_TRIES as u32 && backoff::is_retryable_error(&err) => {
+                tokio::time::sleep(backoff::delay_for(&reqwest::header::HeaderMap::new(), attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// GitHub mints every Actions/App bot's login with this suffix; their
+/// high-volume automated commits/PRs would otherwise inflate a report meant
+/// to count human contributions.
+fn is_bot(user: &User) -> bool {
+    user.login.ends_with("[bot]")
+}
+
+/// Group contributions by canonical contributor identity (see
+/// `Contribution::contributor_key`) rather than the `User` octocrab handed
+/// back for any one of them, so a renamed account's contributions land in
+/// one bucket and id-less commits split by author email instead of all
+/// sharing a single `None` bucket. Each group keeps the first linked `User`
+/// seen for it (`None` if it never saw one) as its representative.
+///
+/// A `Vec` of `(representative, contributions)` pairs, not a `HashMap`,
+/// since distinct identities can share the same `None` representative.
 #[instrument]
 fn contributions_by_user(
     contributions: impl Iterator<Item = Contribution> + fmt::Debug,
-) -> HashMap<Option<User>, Vec<Contribution>> {
-    let mut user_contributions: HashMap<Option<User>, Vec<Contribution>> = HashMap::new();
+    skip_bots: bool,
+) -> Vec<(Option<User>, Vec<Contribution>)> {
+    let mut by_key: HashMap<contribution::ContributorKey, (Option<User>, Vec<Contribution>)> =
+        HashMap::new();
+
     for contribution in contributions {
-        // the hidden `query` field in `User` can be different so will create different keys in the HashMap
-        let entry = if let Some(user) = user_contributions
-            .keys()
-            .find(|user| user.as_ref().map(|u| u.id) == contribution.user().map(|u| u.id))
-        {
-            // mutable_borrow_reservation_conflict: https://github.com/rust-lang/rust/issues/59159
-            let key = user.clone();
-            user_contributions.entry(key)
-        } else {
-            user_contributions.entry(contribution.user().map(|u| u.clone()))
-        };
-        let value = entry.or_insert(Vec::new());
-        (*value).push(contribution);
+        if skip_bots && contribution.user().map(is_bot).unwrap_or(false) {
+            continue;
+        }
+
+        let key = contribution.contributor_key();
+        let entry = by_key
+            .entry(key)
+            .or_insert_with(|| (contribution.user().cloned(), Vec::new()));
+        entry.1.push(contribution);
     }
 
-    user_contributions
+    by_key.into_values().collect()
 }
 
 /// Use GitHub API to check membership. This requires the client TOKEN to have access to the org.
@@ -319,12 +717,104 @@ async fn check_membership(
     Ok(membership)
 }
 
-/// Enrich user with more data from the GitHub API
-#[instrument(skip(client, user))]
+/// Resolve whether `login` belongs to the GitHub org `org_name`, reusing
+/// `cache` so the same (org, user) pair is only ever checked once per run.
+/// Returns `None` instead of failing the whole run when the membership API
+/// has nothing to say (e.g. the token can't see a private org's members),
+/// so callers can fall back to matching the contributor's profile fields.
+#[instrument(skip(client, cache))]
+async fn cached_org_membership(
+    client: &octocrab::Octocrab,
+    cache: &mut HashMap<(String, String), bool>,
+    org_name: &str,
+    login: &str,
+) -> Option<bool> {
+    let key = (org_name.to_string(), login.to_string());
+    if let Some(member) = cache.get(&key) {
+        return Some(*member);
+    }
+
+    match client.orgs(org_name).check_membership(login).await {
+        Ok(member) => {
+            cache.insert(key, member);
+            Some(member)
+        }
+        Err(err) => {
+            warn!(org = org_name, %login, %err, "could not resolve org membership, falling back to profile matching");
+            None
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct TeamMembership {
+    state: String,
+}
+
+/// Resolve whether `login` belongs to `team_slug` within org `org_name`,
+/// reusing `cache` so the same (org, team, user) triple is only ever checked
+/// once per run. Hand-rolled via `cache::fetch` -- octocrab has no handler
+/// for "get team membership for a user" -- rather than through `client`,
+/// so a 404 (not a member) can be told apart from a real failure by status
+/// code instead of string-matching an error message. Returns `None` instead
+/// of failing the whole run when the membership API has nothing to say
+/// (e.g. the token can't see a private team's members), so callers can fall
+/// back to matching the contributor's profile fields.
+#[instrument(skip(http_ctx, cache))]
+async fn cached_team_membership(
+    http_ctx: &cache::HttpContext,
+    cache: &mut HashMap<(String, String, String), bool>,
+    org_name: &str,
+    team_slug: &str,
+    login: &str,
+) -> Option<bool> {
+    let key = (org_name.to_string(), team_slug.to_string(), login.to_string());
+    if let Some(member) = cache.get(&key) {
+        return Some(*member);
+    }
+
+    let url = format!(
+        "{}/orgs/{}/teams/{}/memberships/{}",
+        http_ctx.base_url, org_name, team_slug, login
+    );
+    let member = match cache::fetch(http_ctx, &url).await {
+        Ok(response) if response.status == reqwest::StatusCode::NOT_FOUND => false,
+        Ok(response) if response.status.is_success() => {
+            match serde_json::from_str::<TeamMembership>(&response.body) {
+                Ok(membership) => membership.state == "active",
+                Err(err) => {
+                    warn!(org = org_name, team = team_slug, %login, %err, "could not parse team membership response, falling back to profile matching");
+                    return None;
+                }
+            }
+        }
+        Ok(response) => {
+            warn!(org = org_name, team = team_slug, %login, status = %response.status, "could not resolve team membership, falling back to profile matching");
+            return None;
+        }
+        Err(err) => {
+            warn!(org = org_name, team = team_slug, %login, %err, "could not resolve team membership, falling back to profile matching");
+            return None;
+        }
+    };
+
+    cache.insert(key, member);
+    Some(member)
+}
+
+/// Enrich user with more data from the GitHub API, serving a cached record
+/// instead of calling the API when `store` has one within its TTL.
+#[instrument(skip(client, store, user))]
 async fn enrich_user(
     client: &octocrab::Octocrab,
+    store: Option<&store::Store>,
     user: User,
 ) -> Result<EnrichedUser, octocrab::Error> {
+    let user_id = user.id.0;
+    if let Some(cached) = store.and_then(|store| store.get_user(user_id)) {
+        return Ok(cached);
+    }
+
     let enriched_user = match client
         .get(format!("/users/{}", &user.login), None::<&()>)
         .await
@@ -350,13 +840,17 @@ async fn enrich_user(
         },
     }?;
 
+    if let Some(store) = store {
+        store.put_user(user_id, &enriched_user);
+    }
+
     Ok(enriched_user)
 }
 
 /// Get all the items from the current page until the end
-#[instrument(skip(client, page))]
+#[instrument(skip(http_ctx, page))]
 async fn process_pages<T: DeserializeOwned + fmt::Debug + Send>(
-    client: &octocrab::Octocrab,
+    http_ctx: &cache::HttpContext,
     mut page: Page<T>,
 ) -> Result<Vec<T>, octocrab::Error> {
     let mut items = Vec::new();
@@ -366,7 +860,7 @@ async fn process_pages<T: DeserializeOwned + fmt::Debug + Send>(
             items.push(item);
         }
 
-        let next_page_option = retry_get_page(&client, &page.next, MAX_TRIES).await?;
+        let next_page_option = retry_get_page(http_ctx, &page.next, 0).await?;
         if let Some(next_page) = next_page_option {
             page = next_page;
         } else {
@@ -377,33 +871,43 @@ async fn process_pages<T: DeserializeOwned + fmt::Debug + Send>(
     Ok(items)
 }
 
-/// Stream of Pull Request Reviews
-#[instrument(skip(client, pull_requests))]
+/// Stream of Pull Request Reviews, fetched with up to `concurrency` PRs'
+/// worth of requests in flight at once instead of strictly one at a time.
+#[instrument(skip(client, http_ctx, pull_requests))]
 async fn review_stream(
     client: Arc<octocrab::Octocrab>,
+    http_ctx: Arc<cache::HttpContext>,
     pull_requests: impl Iterator<Item = PullRequest>,
     repo: models::Repo,
+    concurrency: usize,
 ) -> impl Stream<Item = Result<Vec<Review>, octocrab::Error>> {
-    try_stream! {
-        for pull_request in pull_requests {
-            let pull_handler = &client.pulls(&repo.org, &repo.name);
+    let fetches = pull_requests.map(move |pull_request| {
+        let client = client.clone();
+        let http_ctx = http_ctx.clone();
+        let repo = repo.clone();
+        async move {
+            let pull_handler = client.pulls(&repo.org, &repo.name);
             let page = pull_handler.list_reviews(pull_request.number).await?;
-            let items = process_pages(&client, page).await?;
-
-            yield items;
+            process_pages(&http_ctx, page).await
         }
-    }
+    });
+
+    futures::StreamExt::buffer_unordered(stream::iter(fetches), concurrency)
 }
 
 /// Build an output stream
-#[instrument(skip(client))]
+#[instrument(skip(client, http_ctx))]
 async fn output_stream<TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
     client: Arc<octocrab::Octocrab>,
+    http_ctx: Arc<cache::HttpContext>,
+    store: Option<Arc<store::Store>>,
     contributions: impl Iterator<Item = Contribution> + fmt::Debug,
     company_orgs: impl Iterator<Item = impl AsRef<str>> + Clone + fmt::Debug,
+    orgs_exclude: impl Iterator<Item = OrgRegex> + Clone + fmt::Debug,
     repos: impl Iterator<Item = RepoRegex> + Clone + fmt::Debug,
     user_overrides: HashMap<String, config::UserOverride>,
     users_exclude: impl Iterator<Item = impl AsRef<str>> + Clone + fmt::Debug,
+    skip_bots: bool,
     params: Params<TzA, TzB>,
 ) -> impl Stream<Item = Result<Output, octocrab::Error>>
 where
@@ -413,8 +917,10 @@ where
     try_stream! {
         let orgs = company_orgs.clone()
             .map(|org| client.orgs(org.as_ref()));
+        let mut org_membership_cache: HashMap<(String, String), bool> = HashMap::new();
+        let mut team_membership_cache: HashMap<(String, String, String), bool> = HashMap::new();
 
-        for (maybe_user, contributions) in contributions_by_user(contributions) {
+        for (maybe_user, contributions) in contributions_by_user(contributions, skip_bots) {
             let mut membership = false;
             let mut maybe_company_user = None;
             let mut processed_contributions = contributions;
@@ -433,10 +939,64 @@ where
                     };
                     membership = company_orgs.clone().find(|org| org.as_ref() == override_user.company).is_some();
                 } else {
-                    enriched_user = enrich_user(&client, user).await?;
+                    enriched_user = enrich_user(&client, store.as_deref(), user).await?;
                     membership = check_membership(&enriched_user.inner.login, orgs.clone()).await?;
                 }
 
+                // Membership in any configured excluded org is confirmed
+                // against the GitHub API (and cached for reuse across
+                // contributors' shared orgs), regardless of what the
+                // contributor's profile says -- only falling back to
+                // matching `company`/`email` text when that org's
+                // membership list isn't visible to us.
+                for org in orgs_exclude.clone() {
+                    let org_member = cached_org_membership(
+                        &client,
+                        &mut org_membership_cache,
+                        &org.name,
+                        &enriched_user.inner.login,
+                    )
+                    .await;
+
+                    let mut team_member = None;
+                    for team in &org.teams {
+                        match cached_team_membership(
+                            &http_ctx,
+                            &mut team_membership_cache,
+                            &org.name,
+                            team,
+                            &enriched_user.inner.login,
+                        )
+                        .await
+                        {
+                            Some(true) => {
+                                team_member = Some(true);
+                                break;
+                            }
+                            Some(false) if team_member.is_none() => team_member = Some(false),
+                            _ => {}
+                        }
+                    }
+
+                    let affiliated = match (org_member, team_member) {
+                        (Some(true), _) | (_, Some(true)) => true,
+                        (None, None) => org.companies_exclude.iter().any(|exclude_re| {
+                            enriched_user
+                                .company
+                                .as_ref()
+                                .map(|company| exclude_re.company.is_match(company))
+                                .unwrap_or(false)
+                                || enriched_user
+                                    .email
+                                    .as_ref()
+                                    .map(|email| exclude_re.email.is_match(email))
+                                    .unwrap_or(false)
+                        }),
+                        _ => false,
+                    };
+                    membership = membership || affiliated;
+                }
+
                 for config_repo in repos.clone() {
                     processed_contributions = processed_contributions.into_iter().filter(|contribution| {
                         // filter out contributions out of the specified range
@@ -493,9 +1053,9 @@ where
     }
 }
 
-#[instrument(skip(client))]
+#[instrument(skip(http_ctx))]
 async fn issues_page<TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
-    client: Arc<octocrab::Octocrab>,
+    http_ctx: &cache::HttpContext,
     repo: &models::Repo,
     params: &Params<TzA, TzB>,
 ) -> Result<Option<Page<Issue>>, octocrab::Error>
@@ -503,14 +1063,17 @@ where
     TzA::Offset: fmt::Display,
     TzB::Offset: fmt::Display,
 {
-    match client
-        .get(
-            format!("/repos/{}/issues", repo),
-            params.to_params().as_ref(),
-        )
-        .await
-    {
-        Ok(page) => Ok(Some(page)),
+    let url = url::Url::parse_with_params(
+        &format!("{}/repos/{}/issues", http_ctx.base_url, repo),
+        params.to_params().unwrap_or_default(),
+    )
+    .map_err(|source| octocrab::Error::UrlParse {
+        source,
+        backtrace: std::backtrace::Backtrace::capture(),
+    })?;
+
+    match retry_get_page(http_ctx, &Some(url), 0).await {
+        Ok(page) => Ok(page),
         Err(err) => {
             match err {
                 // for cases when the the issues page is turned off
@@ -530,17 +1093,51 @@ where
     }
 }
 
+#[instrument(skip(http_ctx))]
+async fn issue_events_page(
+    http_ctx: &cache::HttpContext,
+    repo: &models::Repo,
+) -> Result<Option<Page<IssueEvent>>, octocrab::Error> {
+    let url = url::Url::parse(&format!("{}/repos/{}/issues/events", http_ctx.base_url, repo))
+        .map_err(|source| octocrab::Error::UrlParse {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+
+    match retry_get_page(http_ctx, &Some(url), 0).await {
+        Ok(page) => Ok(page),
+        Err(err) => {
+            match err {
+                // for cases when the the issues page is turned off
+                octocrab::Error::GitHub { source, backtrace } => {
+                    if source.documentation_url
+                        == "https://docs.github.com/rest/reference/issues#list-issue-events-for-a-repository"
+                    {
+                        eprintln!("Could not fetch issue events: {}", &repo);
+                        Ok(None)
+                    } else {
+                        Err(octocrab::Error::GitHub { source, backtrace })
+                    }
+                }
+                _ => Err(err),
+            }
+        }
+    }
+}
+
 async fn pull_request_page(
     client: Arc<octocrab::Octocrab>,
     repo: &models::Repo,
 ) -> Result<Option<Page<PullRequest>>, octocrab::Error> {
-    let pull_handler = client.pulls(&repo.org, &repo.name);
-    match pull_handler
-        .list()
-        .sort(params::pulls::Sort::Created)
-        .direction(params::Direction::Descending)
-        .send()
-        .await
+    match retry_request(|| {
+        client
+            .pulls(&repo.org, &repo.name)
+            .list()
+            .sort(params::pulls::Sort::Created)
+            .direction(params::Direction::Descending)
+            .send()
+    })
+    .await
     {
         Ok(page) => Ok(Some(page)),
         Err(err) => {
@@ -562,8 +1159,9 @@ async fn pull_request_page(
     }
 }
 
+#[instrument(skip(http_ctx))]
 async fn commit_page<TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
-    client: Arc<octocrab::Octocrab>,
+    http_ctx: &cache::HttpContext,
     repo: &models::Repo,
     params: &Params<TzA, TzB>,
 ) -> Result<Option<Page<EnrichedCommit>>, octocrab::Error>
@@ -571,14 +1169,20 @@ where
     TzA::Offset: fmt::Display,
     TzB::Offset: fmt::Display,
 {
-    match client
-        .get(
-            format!("/repos/{}/{}/commits", repo.org, repo.name),
-            params.to_params().as_ref(),
-        )
-        .await
-    {
-        Ok(page) => Ok(Some(page)),
+    let url = url::Url::parse_with_params(
+        &format!(
+            "{}/repos/{}/{}/commits",
+            http_ctx.base_url, repo.org, repo.name
+        ),
+        params.to_params().unwrap_or_default(),
+    )
+    .map_err(|source| octocrab::Error::UrlParse {
+        source,
+        backtrace: std::backtrace::Backtrace::capture(),
+    })?;
+
+    match retry_get_page(http_ctx, &Some(url), 0).await {
+        Ok(page) => Ok(page),
         Err(err) => {
             match err {
                 // for cases when the the commits page is turned off
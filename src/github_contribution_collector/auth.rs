@@ -0,0 +1,180 @@
+use chrono::{offset::Utc, DateTime, Duration};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// How the collector authenticates to the GitHub API.
+#[derive(Clone)]
+pub enum Auth {
+    /// A personal access token, sent as-is on every request.
+    Token(String),
+    /// A GitHub App installation. The collector mints a short-lived JWT from
+    /// `private_key`, exchanges it for an installation access token at
+    /// `/app/installations/{installation_id}/access_tokens`, and refreshes
+    /// it automatically as it nears expiry -- letting a single App's much
+    /// higher installation rate limit cover every repo the collector
+    /// touches, instead of one long-lived PAT.
+    App {
+        app_id: u64,
+        installation_id: u64,
+        /// PEM-encoded RSA private key for the App.
+        private_key: String,
+    },
+}
+
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Auth::Token(_) => f.debug_tuple("Token").field(&"<redacted>").finish(),
+            Auth::App {
+                app_id,
+                installation_id,
+                ..
+            } => f
+                .debug_struct("App")
+                .field("app_id", app_id)
+                .field("installation_id", installation_id)
+                .field("private_key", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+/// RS256 claims for the App JWT GitHub expects: `iss` is the App ID, and the
+/// JWT itself is only used for the few seconds it takes to mint an
+/// installation access token.
+#[derive(Serialize)]
+struct Claims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct InstallationTokenResponse {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+struct CachedToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Refresh this long before the token's real expiry so an in-flight
+/// multi-page fetch never has a request rejected mid-stream.
+const REFRESH_SKEW_MINUTES: i64 = 2;
+
+/// Mints and caches the bearer token our requests authenticate with. A
+/// plain `Auth::Token` never changes; `Auth::App` mints a fresh installation
+/// access token the first time it's needed, then again once it's within
+/// `REFRESH_SKEW_MINUTES` of expiring.
+pub(super) struct TokenSource {
+    auth: Auth,
+    http: reqwest::Client,
+    base_url: String,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl TokenSource {
+    pub(super) fn new(auth: Auth, http: reqwest::Client, base_url: String) -> Self {
+        Self {
+            auth,
+            http,
+            base_url,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The `Auth` this token source was built from, so callers rebuilding
+    /// against a new API root (GitHub Enterprise Server) can carry it over.
+    pub(super) fn auth(&self) -> &Auth {
+        &self.auth
+    }
+
+    /// The current bearer token, minting or refreshing an installation
+    /// access token first if needed.
+    pub(super) async fn token(&self) -> Result<String, octocrab::Error> {
+        let (app_id, installation_id, private_key) = match &self.auth {
+            Auth::Token(token) => return Ok(token.clone()),
+            Auth::App {
+                app_id,
+                installation_id,
+                private_key,
+            } => (*app_id, *installation_id, private_key),
+        };
+
+        let mut cached = self.cached.lock().await;
+        let needs_refresh = match cached.as_ref() {
+            Some(cached) => Utc::now() + Duration::minutes(REFRESH_SKEW_MINUTES) >= cached.expires_at,
+            None => true,
+        };
+
+        if needs_refresh {
+            *cached = Some(
+                self.mint_installation_token(app_id, installation_id, private_key)
+                    .await?,
+            );
+        }
+
+        Ok(cached.as_ref().expect("just populated above").token.clone())
+    }
+
+    async fn mint_installation_token(
+        &self,
+        app_id: u64,
+        installation_id: u64,
+        private_key: &str,
+    ) -> Result<CachedToken, octocrab::Error> {
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key.as_bytes()).map_err(|source| {
+            octocrab::Error::JWT {
+                source,
+                backtrace: std::backtrace::Backtrace::capture(),
+            }
+        })?;
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            // Allow for a little clock drift between us and GitHub.
+            iat: now - 60,
+            exp: now + 600,
+            iss: app_id.to_string(),
+        };
+        let jwt = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &key,
+        )
+        .map_err(|source| octocrab::Error::JWT {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+
+        let response = self
+            .http
+            .post(format!(
+                "{}/app/installations/{}/access_tokens",
+                self.base_url, installation_id
+            ))
+            .bearer_auth(jwt)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .map_err(|source| octocrab::Error::Http {
+                source,
+                backtrace: std::backtrace::Backtrace::capture(),
+            })?;
+
+        let body: InstallationTokenResponse =
+            response
+                .json()
+                .await
+                .map_err(|source| octocrab::Error::Http {
+                    source,
+                    backtrace: std::backtrace::Backtrace::capture(),
+                })?;
+
+        Ok(CachedToken {
+            token: body.token,
+            expires_at: body.expires_at,
+        })
+    }
+}
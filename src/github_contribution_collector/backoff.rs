@@ -0,0 +1,70 @@
+use chrono::offset::TimeZone;
+use rand::Rng;
+use std::time::Duration;
+
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Statuses that won't succeed no matter how long we wait, so `retry_get_page`
+/// should surface them immediately instead of burning retries.
+pub(super) fn is_permanent(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::UNPROCESSABLE_ENTITY
+    )
+}
+
+/// Whether an `octocrab::Error` from a request that went through octocrab's
+/// own client (rather than `cache::fetch`, where `is_permanent` can check the
+/// real status code) looks like a rate limit we should back off and retry,
+/// rather than a permanent failure to surface right away. Octocrab's
+/// `GitHubError` doesn't carry the response status, so this falls back to
+/// matching the same `documentation_url`/message shape GitHub's primary and
+/// secondary rate-limit errors always use. Transport-level `Http` errors are
+/// retried as well.
+pub(super) fn is_retryable_error(err: &octocrab::Error) -> bool {
+    match err {
+        octocrab::Error::GitHub { source, .. } => {
+            source.documentation_url.contains("rate-limiting")
+                || source.message.to_lowercase().contains("rate limit")
+        }
+        octocrab::Error::Http { .. } => true,
+        _ => false,
+    }
+}
+
+/// How long to wait before trying again, honoring GitHub's primary
+/// rate-limit (`X-RateLimit-Remaining` / `X-RateLimit-Reset`) and
+/// secondary/abuse (`Retry-After`) headers when present, and otherwise
+/// falling back to exponential backoff with full jitter:
+/// `delay = random(0, min(cap, base * 2^attempt))`.
+pub(super) fn delay_for(headers: &reqwest::header::HeaderMap, attempt: u32) -> Duration {
+    if let Some(retry_after) = header_u64(headers, reqwest::header::RETRY_AFTER) {
+        return Duration::from_secs(retry_after);
+    }
+
+    if header_i64(headers, "x-ratelimit-remaining") == Some(0) {
+        if let Some(wait) = rate_limit_reset_delay(headers) {
+            return wait;
+        }
+    }
+
+    let capped = BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_DELAY);
+    rand::thread_rng().gen_range(Duration::ZERO..=capped)
+}
+
+fn rate_limit_reset_delay(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let reset = header_i64(headers, "x-ratelimit-reset")?;
+    let reset_at = chrono::Utc.timestamp_opt(reset, 0).single()?;
+    (reset_at - chrono::Utc::now()).to_std().ok()
+}
+
+fn header_u64(headers: &reqwest::header::HeaderMap, name: impl reqwest::header::AsHeaderName) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+fn header_i64(headers: &reqwest::header::HeaderMap, name: &str) -> Option<i64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
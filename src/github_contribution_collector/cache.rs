@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::Arc,
+};
+use tracing::{instrument, warn};
+
+use super::auth::TokenSource;
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    etag: Option<String>,
+    body: String,
+}
+
+/// On-disk cache of GitHub API response bodies keyed by request URL, so
+/// reruns can send `If-None-Match` and let GitHub answer with a (rate-limit
+/// free) `304 Not Modified` instead of re-downloading unchanged pages.
+#[derive(Debug, Clone)]
+pub(super) struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub(super) fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path_for(&self, url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        self.dir.join(format!("{:x}.json", hasher.finish()))
+    }
+
+    fn load(&self, url: &str) -> Option<(Option<String>, String)> {
+        let data = std::fs::read_to_string(self.path_for(url)).ok()?;
+        let cached: CachedResponse = serde_json::from_str(&data).ok()?;
+        Some((cached.etag, cached.body))
+    }
+
+    fn store(&self, url: &str, etag: Option<String>, body: &str) {
+        let cached = CachedResponse {
+            etag,
+            body: body.to_string(),
+        };
+        match serde_json::to_string(&cached) {
+            Ok(serialized) => {
+                if let Err(err) = std::fs::write(self.path_for(url), serialized) {
+                    warn!(%url, %err, "failed to write response cache entry");
+                }
+            }
+            Err(err) => warn!(%url, %err, "failed to serialize response cache entry"),
+        }
+    }
+}
+
+/// Everything needed to make a GitHub API request ourselves rather than
+/// through octocrab's higher-level helpers: a plain HTTP client (so we can
+/// attach conditional-request headers and read the rate-limit headers
+/// backoff needs from the response), the token source to
+/// authenticate with (refreshed automatically for GitHub App installations),
+/// and an optional on-disk response cache.
+pub(super) struct HttpContext {
+    pub(super) http: reqwest::Client,
+    pub(super) token: Arc<TokenSource>,
+    pub(super) cache: Option<ResponseCache>,
+    /// API root to build hand-rolled request URLs against, e.g.
+    /// `https://api.github.com` or, for GitHub Enterprise Server,
+    /// `https://ghe.example.com/api/v3`. Never has a trailing slash.
+    pub(super) base_url: String,
+}
+
+/// The raw pieces of a response that `retry_get_page` needs to decide
+/// whether (and how long) to wait before trying again.
+pub(super) struct FetchResponse {
+    pub(super) status: reqwest::StatusCode,
+    pub(super) headers: reqwest::header::HeaderMap,
+    pub(super) body: String,
+}
+
+/// Fetch a URL, honoring and updating the on-disk ETag cache when one is
+/// configured. Never turns a non-2xx status into an `Err` itself -- that's
+/// left to the caller, which knows how to retry.
+#[instrument(skip(ctx))]
+pub(super) async fn fetch(ctx: &HttpContext, url: &str) -> Result<FetchResponse, octocrab::Error> {
+    let existing = ctx.cache.as_ref().and_then(|cache| cache.load(url));
+
+    let mut request = ctx
+        .http
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .bearer_auth(ctx.token.token().await?);
+    if let Some((Some(etag), _)) = existing.as_ref() {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|source| octocrab::Error::Http {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+    let status = response.status();
+    let headers = response.headers().clone();
+
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((_, body)) = existing {
+            return Ok(FetchResponse {
+                status,
+                headers,
+                body,
+            });
+        }
+    }
+
+    let etag = headers
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let body = response
+        .text()
+        .await
+        .map_err(|source| octocrab::Error::Http {
+            source,
+            backtrace: std::backtrace::Backtrace::capture(),
+        })?;
+
+    if status.is_success() {
+        if let Some(cache) = ctx.cache.as_ref() {
+            cache.store(url, etag, &body);
+        }
+    }
+
+    Ok(FetchResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Parse GitHub's RFC 8288 `Link` response header -- the only place list
+/// endpoints communicate pagination, since the body itself is a bare JSON
+/// array -- into the `next`/`prev` page URLs.
+pub(super) fn parse_link_header(
+    headers: &reqwest::header::HeaderMap,
+) -> (Option<url::Url>, Option<url::Url>) {
+    let Some(link) = headers.get(reqwest::header::LINK).and_then(|value| value.to_str().ok())
+    else {
+        return (None, None);
+    };
+
+    let mut next = None;
+    let mut prev = None;
+    for part in link.split(',') {
+        let mut segments = part.split(';').map(str::trim);
+        let Some(url) = segments
+            .next()
+            .and_then(|segment| segment.strip_prefix('<'))
+            .and_then(|segment| segment.strip_suffix('>'))
+        else {
+            continue;
+        };
+
+        let rel = segments.find_map(|segment| segment.strip_prefix("rel=").map(|rel| rel.trim_matches('"')));
+        match rel {
+            Some("next") => next = url::Url::parse(url).ok(),
+            Some("prev") => prev = url::Url::parse(url).ok(),
+            _ => {}
+        }
+    }
+
+    (next, prev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_link_header;
+
+    fn headers_with_link(link: &str) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::LINK, link.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn parses_next_and_prev_from_a_multi_page_response() {
+        let headers = headers_with_link(
+            r#"<https://api.github.com/repos/o/r/issues?page=3>; rel="next", <https://api.github.com/repos/o/r/issues?page=1>; rel="prev", <https://api.github.com/repos/o/r/issues?page=5>; rel="last""#,
+        );
+
+        let (next, prev) = parse_link_header(&headers);
+
+        assert_eq!(
+            next.map(|url| url.to_string()),
+            Some("https://api.github.com/repos/o/r/issues?page=3".to_string())
+        );
+        assert_eq!(
+            prev.map(|url| url.to_string()),
+            Some("https://api.github.com/repos/o/r/issues?page=1".to_string())
+        );
+    }
+
+    #[test]
+    fn returns_none_for_a_last_page_response_with_no_link_header() {
+        let headers = reqwest::header::HeaderMap::new();
+
+        assert_eq!(parse_link_header(&headers), (None, None));
+    }
+
+    #[test]
+    fn ignores_unrecognized_rels() {
+        let headers = headers_with_link(
+            r#"<https://api.github.com/repos/o/r/issues?page=1>; rel="first""#,
+        );
+
+        assert_eq!(parse_link_header(&headers), (None, None));
+    }
+}
@@ -0,0 +1,649 @@
+use crate::{
+    models::{self, commit::EnrichedCommit},
+    Contribution,
+};
+use chrono::{offset::Utc, DateTime, TimeZone};
+use octocrab::models::{issues::Issue, pulls::Review};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::instrument;
+
+/// GraphQL query mirroring `reviews()`'s REST shape, but pulling every pull
+/// request's reviews alongside it in a single cursor-paginated round trip
+/// instead of one `list_reviews` call per pull request.
+const REVIEWS_QUERY: &str = r#"
+query($owner: String!, $name: String!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    pullRequests(first: 50, after: $cursor) {
+      pageInfo {
+        hasNextPage
+        endCursor
+      }
+      nodes {
+        number
+        reviews(first: 100) {
+          nodes {
+            databaseId
+            state
+            body
+            submittedAt
+            url
+            commit {
+              oid
+            }
+            author {
+              login
+              ... on User {
+                databaseId
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Serialize)]
+struct ReviewsVariables<'a> {
+    owner: &'a str,
+    name: &'a str,
+    cursor: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct GraphqlBody<'a> {
+    query: &'a str,
+    variables: ReviewsVariables<'a>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewsData {
+    repository: ReviewsRepository,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewsRepository {
+    #[serde(rename = "pullRequests")]
+    pull_requests: PullRequestConnection,
+}
+
+#[derive(Deserialize, Debug)]
+struct PullRequestConnection {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<PullRequestNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PageInfo {
+    #[serde(rename = "hasNextPage")]
+    has_next_page: bool,
+    #[serde(rename = "endCursor")]
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PullRequestNode {
+    number: u64,
+    reviews: ReviewConnection,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewConnection {
+    nodes: Vec<ReviewNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewNode {
+    #[serde(rename = "databaseId")]
+    database_id: Option<u64>,
+    state: String,
+    body: Option<String>,
+    #[serde(rename = "submittedAt")]
+    submitted_at: Option<DateTime<Utc>>,
+    url: String,
+    commit: Option<ReviewCommit>,
+    author: Option<ReviewAuthor>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewCommit {
+    oid: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewAuthor {
+    login: String,
+    #[serde(rename = "databaseId")]
+    database_id: Option<u64>,
+}
+
+/// Build a GitHub "simple user" REST object from the handful of fields
+/// GraphQL's `author` gives us, so the rest of the pipeline (which expects
+/// `octocrab::models::User`) doesn't need to know a review came from GraphQL.
+fn user_json(author: &ReviewAuthor) -> serde_json::Value {
+    let login = &author.login;
+    let id = author.database_id.unwrap_or_default();
+
+    serde_json::json!({
+        "login": login,
+        "id": id,
+        "node_id": format!("U_{}", id),
+        "avatar_url": format!("https://avatars.githubusercontent.com/u/{}?v=4", id),
+        "gravatar_id": "",
+        "url": format!("https://api.github.com/users/{}", login),
+        "html_url": format!("https://github.com/{}", login),
+        "followers_url": format!("https://api.github.com/users/{}/followers", login),
+        "following_url": format!("https://api.github.com/users/{}/following{{/other_user}}", login),
+        "gists_url": format!("https://api.github.com/users/{}/gists{{/gist_id}}", login),
+        "starred_url": format!("https://api.github.com/users/{}/starred{{/owner}}{{/repo}}", login),
+        "subscriptions_url": format!("https://api.github.com/users/{}/subscriptions", login),
+        "organizations_url": format!("https://api.github.com/users/{}/orgs", login),
+        "repos_url": format!("https://api.github.com/users/{}/repos", login),
+        "events_url": format!("https://api.github.com/users/{}/events{{/privacy}}", login),
+        "received_events_url": format!("https://api.github.com/users/{}/received_events", login),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn review_json(repo: &models::Repo, pr_number: u64, node: &ReviewNode) -> serde_json::Value {
+    let id = node.database_id.unwrap_or_default();
+    let pull_request_url = format!(
+        "https://api.github.com/repos/{}/{}/pulls/{}",
+        repo.org, repo.name, pr_number
+    );
+
+    serde_json::json!({
+        "id": id,
+        "node_id": format!("PRR_{}", id),
+        "user": node.author.as_ref().map(user_json),
+        "body": node.body,
+        "state": node.state,
+        "html_url": node.url,
+        "pull_request_url": pull_request_url,
+        "_links": {
+            "html": { "href": node.url },
+            "pull_request": { "href": pull_request_url },
+        },
+        "submitted_at": node.submitted_at,
+        "commit_id": node.commit.as_ref().map(|commit| commit.oid.clone()).unwrap_or_default(),
+        "author_association": "NONE",
+    })
+}
+
+/// Page through a repo's pull requests via GraphQL, pulling each one's
+/// reviews inline so no per-pull-request `list_reviews` round trip is
+/// needed. Reviews are converted back into the REST `Review` shape so
+/// callers don't have to care which API produced them.
+#[instrument(skip(client))]
+pub(super) async fn reviews(
+    client: &octocrab::Octocrab,
+    repo: &models::Repo,
+) -> Result<Vec<Review>, octocrab::Error> {
+    let mut reviews = vec![];
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let body = GraphqlBody {
+            query: REVIEWS_QUERY,
+            variables: ReviewsVariables {
+                owner: &repo.org,
+                name: &repo.name,
+                cursor: cursor.as_deref(),
+            },
+        };
+
+        let data: ReviewsData = super::retry_request(|| client.graphql(&body)).await?;
+
+        for pull_request in &data.repository.pull_requests.nodes {
+            for node in &pull_request.reviews.nodes {
+                reviews.push(from_value(review_json(repo, pull_request.number, node))?);
+            }
+        }
+
+        let page_info = data.repository.pull_requests.page_info;
+        if page_info.has_next_page {
+            cursor = page_info.end_cursor;
+        } else {
+            break;
+        }
+    }
+
+    Ok(reviews)
+}
+
+/// GraphQL query mirroring `contributions()`'s REST shape (commits, issues,
+/// and reviews, all in one round trip per page), with each connection
+/// paginated by its own cursor and dropped from the selection via
+/// `@include` once it's exhausted, so a repo that runs out of issues before
+/// commits stops asking for issues without restarting the other cursors.
+const CONTRIBUTIONS_QUERY: &str = r#"
+query(
+  $owner: String!
+  $name: String!
+  $since: GitTimestamp
+  $until: GitTimestamp
+  $issuesSince: DateTime
+  $commitsCursor: String
+  $issuesCursor: String
+  $reviewsCursor: String
+  $fetchCommits: Boolean!
+  $fetchIssues: Boolean!
+  $fetchReviews: Boolean!
+) {
+  repository(owner: $owner, name: $name) {
+    defaultBranchRef @include(if: $fetchCommits) {
+      target {
+        ... on Commit {
+          history(first: 100, after: $commitsCursor, since: $since, until: $until) {
+            pageInfo {
+              hasNextPage
+              endCursor
+            }
+            nodes {
+              oid
+              committedDate
+              author {
+                name
+                email
+                user {
+                  login
+                  ... on User {
+                    databaseId
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+    issues(first: 100, after: $issuesCursor, filterBy: { since: $issuesSince }, orderBy: { field: CREATED_AT, direction: ASC }) @include(if: $fetchIssues) {
+      pageInfo {
+        hasNextPage
+        endCursor
+      }
+      nodes {
+        databaseId
+        number
+        title
+        body
+        state
+        createdAt
+        updatedAt
+        author {
+          login
+          ... on User {
+            databaseId
+          }
+        }
+      }
+    }
+    pullRequests(first: 100, after: $reviewsCursor) @include(if: $fetchReviews) {
+      pageInfo {
+        hasNextPage
+        endCursor
+      }
+      nodes {
+        number
+        reviews(first: 100) {
+          nodes {
+            databaseId
+            state
+            body
+            submittedAt
+            url
+            commit {
+              oid
+            }
+            author {
+              login
+              ... on User {
+                databaseId
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+// Owned (rather than borrowing from `cursor`/`repo`) so a `ContributionsBody`
+// can be kept alive in its own variable across a loop iteration boundary --
+// the request future for page N+1 is created before page N's nodes are
+// mapped and only awaited on the next iteration, so its backing data can't
+// borrow from a `cursor` that's about to be mutated again.
+#[derive(Serialize)]
+struct ContributionsVariables {
+    owner: String,
+    name: String,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    #[serde(rename = "issuesSince")]
+    issues_since: Option<DateTime<Utc>>,
+    #[serde(rename = "commitsCursor")]
+    commits_cursor: Option<String>,
+    #[serde(rename = "issuesCursor")]
+    issues_cursor: Option<String>,
+    #[serde(rename = "reviewsCursor")]
+    reviews_cursor: Option<String>,
+    #[serde(rename = "fetchCommits")]
+    fetch_commits: bool,
+    #[serde(rename = "fetchIssues")]
+    fetch_issues: bool,
+    #[serde(rename = "fetchReviews")]
+    fetch_reviews: bool,
+}
+
+#[derive(Serialize)]
+struct ContributionsBody {
+    query: &'static str,
+    variables: ContributionsVariables,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ContributionsData {
+    #[serde(default)]
+    repository: ContributionsRepository,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ContributionsRepository {
+    #[serde(rename = "defaultBranchRef", default)]
+    default_branch_ref: Option<DefaultBranchRef>,
+    #[serde(default)]
+    issues: Option<IssueConnection>,
+    #[serde(rename = "pullRequests", default)]
+    pull_requests: Option<PullRequestConnection>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DefaultBranchRef {
+    target: Option<CommitHistoryTarget>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitHistoryTarget {
+    history: CommitConnection,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitConnection {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<CommitNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitNode {
+    oid: String,
+    #[serde(rename = "committedDate")]
+    committed_date: DateTime<Utc>,
+    author: Option<CommitAuthor>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitAuthor {
+    name: Option<String>,
+    email: Option<String>,
+    user: Option<ReviewAuthor>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IssueConnection {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<IssueNode>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IssueNode {
+    #[serde(rename = "databaseId")]
+    database_id: Option<u64>,
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    #[serde(rename = "createdAt")]
+    created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    updated_at: DateTime<Utc>,
+    author: Option<ReviewAuthor>,
+}
+
+/// Build a GitHub "commit" REST object from the handful of fields GraphQL's
+/// commit history gives us, mirroring `review_json`'s approach so the rest
+/// of the pipeline doesn't need to know a commit came from GraphQL.
+fn commit_json(repo: &models::Repo, node: &CommitNode) -> serde_json::Value {
+    let sha = &node.oid;
+    let name = node.author.as_ref().and_then(|a| a.name.clone()).unwrap_or_default();
+    let email = node.author.as_ref().and_then(|a| a.email.clone()).unwrap_or_default();
+    let author_user = node.author.as_ref().and_then(|a| a.user.as_ref()).map(user_json);
+
+    serde_json::json!({
+        "sha": sha,
+        "node_id": format!("C_{}", sha),
+        "url": format!("https://api.github.com/repos/{}/{}/commits/{}", repo.org, repo.name, sha),
+        "html_url": format!("https://github.com/{}/{}/commit/{}", repo.org, repo.name, sha),
+        "comments_url": format!("https://api.github.com/repos/{}/{}/commits/{}/comments", repo.org, repo.name, sha),
+        "commit": {
+            "url": format!("https://api.github.com/repos/{}/{}/git/commits/{}", repo.org, repo.name, sha),
+            "author": { "name": name, "email": email, "date": node.committed_date },
+            "committer": { "name": name, "email": email, "date": node.committed_date },
+            "message": "",
+            "tree": { "sha": sha, "url": "" },
+            "comment_count": 0,
+        },
+        "author": author_user,
+        "committer": author_user,
+        "parents": [],
+    })
+}
+
+/// Build a GitHub "issue" REST object from the handful of fields GraphQL's
+/// issue connection gives us, mirroring `review_json`'s approach so the
+/// rest of the pipeline doesn't need to know an issue came from GraphQL.
+fn issue_json(repo: &models::Repo, node: &IssueNode) -> serde_json::Value {
+    let id = node.database_id.unwrap_or_default();
+
+    serde_json::json!({
+        "id": id,
+        "node_id": format!("I_{}", id),
+        "url": format!("https://api.github.com/repos/{}/{}/issues/{}", repo.org, repo.name, node.number),
+        "repository_url": format!("https://api.github.com/repos/{}/{}", repo.org, repo.name),
+        "labels_url": format!("https://api.github.com/repos/{}/{}/issues/{}/labels{{/name}}", repo.org, repo.name, node.number),
+        "comments_url": format!("https://api.github.com/repos/{}/{}/issues/{}/comments", repo.org, repo.name, node.number),
+        "events_url": format!("https://api.github.com/repos/{}/{}/issues/{}/events", repo.org, repo.name, node.number),
+        "html_url": format!("https://github.com/{}/{}/issues/{}", repo.org, repo.name, node.number),
+        "number": node.number,
+        "state": node.state.to_lowercase(),
+        "title": node.title,
+        "body": node.body,
+        "user": node.author.as_ref().map(user_json),
+        "labels": [],
+        "assignee": serde_json::Value::Null,
+        "assignees": [],
+        "milestone": serde_json::Value::Null,
+        "locked": false,
+        "active_lock_reason": serde_json::Value::Null,
+        "comments": 0,
+        "pull_request": serde_json::Value::Null,
+        "closed_at": serde_json::Value::Null,
+        "created_at": node.created_at,
+        "updated_at": node.updated_at,
+        "closed_by": serde_json::Value::Null,
+        "author_association": "NONE",
+    })
+}
+
+/// Which of the three connections still have more pages to fetch.
+#[derive(Default)]
+struct ContributionsCursor {
+    commits: Option<String>,
+    issues: Option<String>,
+    reviews: Option<String>,
+    commits_done: bool,
+    issues_done: bool,
+    reviews_done: bool,
+}
+
+impl ContributionsCursor {
+    fn pending(&self) -> bool {
+        !self.commits_done || !self.issues_done || !self.reviews_done
+    }
+
+    /// `data` is the response to a request built from this cursor, so a
+    /// connection that was requested (not yet done) but came back null --
+    /// e.g. an empty repo with no default branch -- has no pages left.
+    fn advance(&mut self, data: &ContributionsData) {
+        if !self.commits_done {
+            match data
+                .repository
+                .default_branch_ref
+                .as_ref()
+                .and_then(|default_branch_ref| default_branch_ref.target.as_ref())
+            {
+                Some(target) if target.history.page_info.has_next_page => {
+                    self.commits = target.history.page_info.end_cursor.clone();
+                }
+                _ => self.commits_done = true,
+            }
+        }
+
+        if !self.issues_done {
+            match data.repository.issues.as_ref() {
+                Some(issues) if issues.page_info.has_next_page => {
+                    self.issues = issues.page_info.end_cursor.clone();
+                }
+                _ => self.issues_done = true,
+            }
+        }
+
+        if !self.reviews_done {
+            match data.repository.pull_requests.as_ref() {
+                Some(pull_requests) if pull_requests.page_info.has_next_page => {
+                    self.reviews = pull_requests.page_info.end_cursor.clone();
+                }
+                _ => self.reviews_done = true,
+            }
+        }
+    }
+}
+
+fn contributions_body(
+    repo: &models::Repo,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    cursor: &ContributionsCursor,
+) -> ContributionsBody {
+    ContributionsBody {
+        query: CONTRIBUTIONS_QUERY,
+        variables: ContributionsVariables {
+            owner: repo.org.clone(),
+            name: repo.name.clone(),
+            since,
+            until,
+            issues_since: since,
+            commits_cursor: cursor.commits.clone(),
+            issues_cursor: cursor.issues.clone(),
+            reviews_cursor: cursor.reviews.clone(),
+            fetch_commits: !cursor.commits_done,
+            fetch_issues: !cursor.issues_done,
+            fetch_reviews: !cursor.reviews_done,
+        },
+    }
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Result<T, octocrab::Error> {
+    serde_json::from_value(value).map_err(|source| octocrab::Error::Serde {
+        source,
+        backtrace: std::backtrace::Backtrace::capture(),
+    })
+}
+
+fn extend_from_page(
+    repo: &models::Repo,
+    contributions: &mut Vec<Contribution>,
+    data: &ContributionsData,
+) -> Result<(), octocrab::Error> {
+    if let Some(target) = data
+        .repository
+        .default_branch_ref
+        .as_ref()
+        .and_then(|default_branch_ref| default_branch_ref.target.as_ref())
+    {
+        for node in &target.history.nodes {
+            let commit: EnrichedCommit = from_value(commit_json(repo, node))?;
+            contributions.push(Contribution::new(&repo.org, &repo.name, commit.into()));
+        }
+    }
+
+    if let Some(issues) = data.repository.issues.as_ref() {
+        for node in &issues.nodes {
+            let issue: Issue = from_value(issue_json(repo, node))?;
+            contributions.push(Contribution::new(&repo.org, &repo.name, issue.into()));
+        }
+    }
+
+    if let Some(pull_requests) = data.repository.pull_requests.as_ref() {
+        for pull_request in &pull_requests.nodes {
+            for node in &pull_request.reviews.nodes {
+                let review: Review = from_value(review_json(repo, pull_request.number, node))?;
+                contributions.push(Contribution::new(&repo.org, &repo.name, review.into()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Page through a repo's commits, issues, and reviews in a single GraphQL
+/// query shape, one page of each connection per round trip. The request
+/// future for page N+1 is built as soon as page N's cursors are known, but
+/// it isn't polled -- and so isn't sent -- until page N's nodes have been
+/// mapped into `Contribution`s; round trips run one after another and don't
+/// overlap with that mapping.
+#[instrument(skip(client, params))]
+pub(super) async fn contributions<TzA, TzB>(
+    client: &octocrab::Octocrab,
+    repo: &models::Repo,
+    params: &super::Params<TzA, TzB>,
+) -> Result<Vec<Contribution>, octocrab::Error>
+where
+    TzA: TimeZone + fmt::Debug,
+    TzB: TimeZone + fmt::Debug,
+    TzA::Offset: fmt::Display,
+    TzB::Offset: fmt::Display,
+{
+    let since = params.since.as_ref().map(|since| since.with_timezone(&Utc));
+    let until = params.until.as_ref().map(|until| until.with_timezone(&Utc));
+
+    let mut contributions = Vec::new();
+    let mut cursor = ContributionsCursor::default();
+    let mut body = contributions_body(repo, since, until, &cursor);
+    let mut next_request = super::retry_request(|| client.graphql(&body));
+
+    loop {
+        let data: ContributionsData = next_request.await?;
+
+        cursor.advance(&data);
+        let more_pages = cursor.pending();
+        if more_pages {
+            body = contributions_body(repo, since, until, &cursor);
+            next_request = super::retry_request(|| client.graphql(&body));
+        }
+
+        extend_from_page(repo, &mut contributions, &data)?;
+
+        if !more_pages {
+            break;
+        }
+    }
+
+    Ok(contributions)
+}
@@ -0,0 +1,266 @@
+use crate::{models, models::EnrichedUser, Contribution};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+use tracing::{instrument, warn};
+
+/// Persists `EnrichedUser`s (keyed by their stable GitHub user id) and
+/// fetched `Contribution`s (keyed by `(repo, kind, id)`) across runs, so an
+/// audit over overlapping date windows only enriches users once per `ttl`,
+/// and records the last stored `created_at` per repo+kind for the collector
+/// to narrow later requests with.
+pub(super) struct Store {
+    conn: Mutex<Connection>,
+    ttl: Duration,
+}
+
+impl Store {
+    pub(super) fn open(path: impl AsRef<std::path::Path>, ttl: Duration) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS enriched_users (
+                id INTEGER PRIMARY KEY,
+                body TEXT NOT NULL,
+                cached_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS contributions (
+                repo_org TEXT NOT NULL,
+                repo_name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                id TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                body TEXT NOT NULL,
+                PRIMARY KEY (repo_org, repo_name, kind, id)
+            );
+            ",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            ttl,
+        })
+    }
+
+    /// A cached `EnrichedUser`, if one was stored within `ttl`.
+    #[instrument(skip(self))]
+    pub(super) fn get_user(&self, id: u64) -> Option<EnrichedUser> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(String, DateTime<Utc>)> = conn
+            .query_row(
+                "SELECT body, cached_at FROM enriched_users WHERE id = ?1",
+                params![id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .unwrap_or(None);
+
+        let (body, cached_at) = row?;
+        if Utc::now() - cached_at > self.ttl {
+            return None;
+        }
+
+        serde_json::from_str(&body)
+            .map_err(|err| warn!(%id, %err, "failed to deserialize cached enriched user"))
+            .ok()
+    }
+
+    #[instrument(skip(self, user))]
+    pub(super) fn put_user(&self, id: u64, user: &EnrichedUser) {
+        let body = match serde_json::to_string(user) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!(%id, %err, "failed to serialize enriched user for caching");
+                return;
+            }
+        };
+
+        let conn = self.conn.lock().unwrap();
+        if let Err(err) = conn.execute(
+            "INSERT INTO enriched_users (id, body, cached_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET body = excluded.body, cached_at = excluded.cached_at",
+            params![id as i64, body, Utc::now()],
+        ) {
+            warn!(%id, %err, "failed to write enriched user to cache");
+        }
+    }
+
+    /// The most recent `created_at`/`updated_at` stored for this
+    /// repo+kind, so the next fetch can ask the API for only what's
+    /// changed since then.
+    #[instrument(skip(self))]
+    pub(super) fn last_updated(&self, repo: &models::Repo, kind: &str) -> Option<DateTime<Utc>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT MAX(updated_at) FROM contributions WHERE repo_org = ?1 AND repo_name = ?2 AND kind = ?3",
+            params![repo.org, repo.name, kind],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap_or(None)
+        .flatten()
+    }
+
+    /// Every contribution of `kind` previously cached for this repo.
+    #[instrument(skip(self))]
+    pub(super) fn contributions(&self, repo: &models::Repo, kind: &str) -> Vec<Contribution> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = match conn.prepare(
+            "SELECT body FROM contributions WHERE repo_org = ?1 AND repo_name = ?2 AND kind = ?3",
+        ) {
+            Ok(stmt) => stmt,
+            Err(err) => {
+                warn!(%err, "failed to query cached contributions");
+                return vec![];
+            }
+        };
+
+        let rows = match stmt.query_map(params![repo.org, repo.name, kind], |row| {
+            row.get::<_, String>(0)
+        }) {
+            Ok(rows) => rows,
+            Err(err) => {
+                warn!(%err, "failed to read cached contributions");
+                return vec![];
+            }
+        };
+
+        rows.filter_map(Result::ok)
+            .filter_map(|body| serde_json::from_str(&body).ok())
+            .collect()
+    }
+
+    /// Write freshly-fetched contributions back, replacing any row already
+    /// cached for the same id. One transaction per repo+kind, so a crash
+    /// mid-sync never leaves a repo's cache half-updated.
+    #[instrument(skip(self, items))]
+    pub(super) fn put_contributions(
+        &self,
+        repo: &models::Repo,
+        kind: &str,
+        items: &[Contribution],
+    ) -> rusqlite::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for item in items {
+            let Some(updated_at) = item.created_at() else {
+                continue;
+            };
+            let id = item.contribution.id();
+            let body = serde_json::to_string(item).expect("Contribution is always serializable");
+
+            tx.execute(
+                "INSERT INTO contributions (repo_org, repo_name, kind, id, updated_at, body)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(repo_org, repo_name, kind, id) DO UPDATE SET
+                    updated_at = excluded.updated_at, body = excluded.body",
+                params![repo.org, repo.name, kind, id, updated_at, body],
+            )?;
+        }
+
+        tx.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contribution::GithubContribution;
+
+    fn commit_contribution(repo: &models::Repo, sha: &str, committed_at: DateTime<Utc>) -> Contribution {
+        let commit = serde_json::from_value(serde_json::json!({
+            "sha": sha,
+            "node_id": format!("C_{}", sha),
+            "url": format!("https://api.github.com/repos/{}/{}/commits/{}", repo.org, repo.name, sha),
+            "html_url": format!("https://github.com/{}/{}/commit/{}", repo.org, repo.name, sha),
+            "comments_url": format!("https://api.github.com/repos/{}/{}/commits/{}/comments", repo.org, repo.name, sha),
+            "commit": {
+                "url": format!("https://api.github.com/repos/{}/{}/git/commits/{}", repo.org, repo.name, sha),
+                "author": { "name": "Author", "email": "author@example.com", "date": committed_at },
+                "committer": { "name": "Author", "email": "author@example.com", "date": committed_at },
+                "message": "",
+                "tree": { "sha": sha, "url": "" },
+                "comment_count": 0,
+            },
+            "author": serde_json::Value::Null,
+            "committer": serde_json::Value::Null,
+            "parents": [],
+        }))
+        .expect("fixture matches EnrichedCommit's shape");
+
+        Contribution::new(&repo.org, &repo.name, GithubContribution::Commit(commit))
+    }
+
+    #[test]
+    fn get_user_is_none_before_the_first_put() {
+        let store = Store::open(":memory:", Duration::hours(24)).unwrap();
+
+        assert!(store.get_user(1).is_none());
+    }
+
+    #[test]
+    fn get_user_is_none_once_the_cached_entry_is_older_than_ttl() {
+        let store = Store::open(":memory:", Duration::hours(24)).unwrap();
+        let user = EnrichedUser {
+            inner: serde_json::from_value(serde_json::json!({
+                "login": "octocat", "id": 1, "node_id": "U_1",
+                "avatar_url": "", "gravatar_id": "", "url": "", "html_url": "",
+                "followers_url": "", "following_url": "", "gists_url": "",
+                "starred_url": "", "subscriptions_url": "", "organizations_url": "",
+                "repos_url": "", "events_url": "", "received_events_url": "",
+                "type": "User", "site_admin": false,
+            }))
+            .unwrap(),
+            company: None,
+            email: None,
+        };
+        store.put_user(1, &user);
+        assert!(store.get_user(1).is_some());
+
+        // Backdate the cache entry past the TTL directly, since `put_user`
+        // always stamps `cached_at` with the current time.
+        let conn = store.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE enriched_users SET cached_at = ?1 WHERE id = 1",
+            params![Utc::now() - Duration::hours(25)],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert!(store.get_user(1).is_none());
+    }
+
+    #[test]
+    fn put_contributions_overwrites_rather_than_duplicates_an_existing_id() {
+        let store = Store::open(":memory:", Duration::hours(24)).unwrap();
+        let repo = models::Repo::new("org", "repo");
+        let first = commit_contribution(&repo, "abc123", "2024-01-01T00:00:00Z".parse().unwrap());
+        let updated = commit_contribution(&repo, "abc123", "2024-01-02T00:00:00Z".parse().unwrap());
+
+        store.put_contributions(&repo, "commit", &[first]).unwrap();
+        store.put_contributions(&repo, "commit", &[updated]).unwrap();
+
+        let cached = store.contributions(&repo, "commit");
+        assert_eq!(cached.len(), 1);
+        assert_eq!(
+            cached[0].created_at(),
+            Some("2024-01-02T00:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn last_updated_is_the_max_across_every_cached_contribution() {
+        let store = Store::open(":memory:", Duration::hours(24)).unwrap();
+        let repo = models::Repo::new("org", "repo");
+        let older = commit_contribution(&repo, "older", "2024-01-01T00:00:00Z".parse().unwrap());
+        let newer = commit_contribution(&repo, "newer", "2024-06-01T00:00:00Z".parse().unwrap());
+
+        store.put_contributions(&repo, "commit", &[older, newer]).unwrap();
+
+        assert_eq!(
+            store.last_updated(&repo, "commit"),
+            Some("2024-06-01T00:00:00Z".parse().unwrap())
+        );
+    }
+}
@@ -1,7 +1,10 @@
+pub mod cli;
 pub mod config;
 pub mod contribution;
+pub mod export;
 pub mod github_contribution_collector;
 pub mod models;
+pub mod report;
 
 pub use contribution::Contribution;
 pub use github_contribution_collector::GithubContributionCollector;
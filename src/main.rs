@@ -1,34 +1,76 @@
-use async_stream::try_stream;
 use chrono::offset::TimeZone;
-use futures::{future::join_all, Stream};
+use futures::{stream, Stream};
 use github_contributions::{
-    cli, config::Config, contribution::GithubContribution, github_contribution_collector::Params,
-    models::Repo, Contribution, GithubContributionCollector,
+    cli,
+    config::Config,
+    export::{self, ExportFormat},
+    github_contribution_collector::{Auth, Params},
+    report::{self, ReportFormat},
+    Contribution, GithubContributionCollector,
 };
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{fmt, sync::Arc};
 use structopt::StructOpt;
 use tokio_stream::StreamExt;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
-async fn contributions_stream<TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
+/// Fetch every repo's contributions, with up to `client`'s configured
+/// concurrency limit of repos in flight at once instead of waiting on all
+/// of them together.
+fn contributions_stream<'a, TzA: TimeZone + fmt::Debug, TzB: TimeZone + fmt::Debug>(
     client: Arc<GithubContributionCollector>,
-    repos: impl Iterator<Item = &github_contributions::config::Repo>,
+    repos: impl Iterator<Item = &'a github_contributions::config::Repo>,
     params: Params<TzA, TzB>,
-) -> impl Stream<Item = Result<Vec<Contribution>, octocrab::Error>>
+) -> impl Stream<Item = Result<Vec<Contribution>, octocrab::Error>> + 'a
 where
     TzA::Offset: fmt::Display,
+    TzA: 'a,
     TzB::Offset: fmt::Display,
+    TzB: 'a,
 {
-    try_stream! {
-        let mut tasks = vec![];
-        // queue up all tasks first
-        for repo in repos {
-            tasks.push(client.contributions(&repo.repo.org, &repo.repo.name, &params));
+    let concurrency = client.concurrency();
+    let fetches = repos.map(move |repo| {
+        let client = client.clone();
+        let params = params.clone();
+        async move {
+            client
+                .contributions(&repo.repo, &params)
+                .await
         }
-        for result in join_all(tasks).await {
-            let contributions = result?;
-            yield contributions;
+    });
+
+    futures::StreamExt::buffer_unordered(stream::iter(fetches), concurrency)
+}
+
+/// Resolve how to authenticate: GitHub App credentials (CLI overriding the
+/// config file) if a full set is given, a `GITHUB_TOKEN` if none of them is,
+/// and an error for a partial set.
+fn resolve_auth(args: &cli::Opt, config: &Config) -> anyhow::Result<Auth> {
+    let app_id = args.app_id.or_else(|| config.github_app.as_ref().map(|a| a.app_id));
+    let installation_id = args
+        .app_installation_id
+        .or_else(|| config.github_app.as_ref().map(|a| a.installation_id));
+    let private_key_path = args
+        .app_private_key
+        .clone()
+        .or_else(|| config.github_app.as_ref().map(|a| a.private_key_path.clone()));
+
+    match (app_id, installation_id, private_key_path) {
+        (Some(app_id), Some(installation_id), Some(private_key_path)) => Ok(Auth::App {
+            app_id,
+            installation_id,
+            private_key: std::fs::read_to_string(private_key_path)?,
+        }),
+        (None, None, None) => {
+            let github_token = std::env::var("GITHUB_TOKEN").unwrap_or_else(|_| {
+                eprintln!("Please provide a GITHUB_TOKEN, or GitHub App credentials via --app-id/--app-installation-id/--app-private-key");
+
+                std::process::exit(1);
+            });
+            Ok(Auth::Token(github_token))
         }
+        _ => anyhow::bail!(
+            "GitHub App auth requires all of app_id, installation_id, and private_key_path"
+        ),
     }
 }
 
@@ -39,20 +81,36 @@ async fn main() -> anyhow::Result<()> {
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    let github_token = std::env::var("GITHUB_TOKEN").unwrap_or_else(|_| {
-        eprintln!("Please provide a GITHUB_TOKEN");
-
-        std::process::exit(1);
-    });
     let args = cli::Opt::from_args();
     let config: Config = toml::from_str(&std::fs::read_to_string(&args.config)?)?;
-    let client = Arc::new(GithubContributionCollector::new(Some(github_token))?);
+    let auth = resolve_auth(&args, &config)?;
+    let mut client = GithubContributionCollector::new(auth)
+        .await?
+        .with_concurrency(args.concurrency)
+        .with_graphql(args.graphql || config.graphql);
+    if let Some(github_url) = args.github_url.as_ref().or(config.github_url.as_ref()) {
+        client = client.with_github_url(github_url).await?;
+    }
+    if let Some(response_cache_dir) = args
+        .response_cache_dir
+        .as_ref()
+        .or(config.response_cache_dir.as_ref())
+    {
+        client = client.with_cache(response_cache_dir.clone())?;
+    }
+    if let Some(cache_db) = args.cache_db.as_ref().or(config.cache.as_ref().map(|c| &c.path)) {
+        let ttl_hours = args
+            .cache_ttl_hours
+            .or(config.cache.as_ref().map(|c| c.ttl_hours))
+            .unwrap_or(24);
+        client = client.with_sqlite_cache(cache_db, chrono::Duration::hours(ttl_hours))?;
+    }
+    let client = Arc::new(client);
     let params = Params {
         since: args.start,
         until: args.end,
     };
     let contributions = contributions_stream(client.clone(), config.repos.iter(), params.clone())
-        .await
         .collect::<Result<Vec<Vec<Contribution>>, octocrab::Error>>()
         .await?
         .into_iter()
@@ -61,9 +119,11 @@ async fn main() -> anyhow::Result<()> {
         .process_contributions(
             contributions.into_iter(),
             config.company_organizations.iter(),
+            config.orgs.iter(),
             config.repos.iter(),
             config.user_overrides.into_iter(),
             config.users_exclude.iter(),
+            config.skip_bots,
             params.clone(),
         )
         .await?;
@@ -76,86 +136,20 @@ async fn main() -> anyhow::Result<()> {
             .unwrap()
     });
 
-    println!(
-        "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10}",
-        "handle", "issues", "reviews", "commits", "all"
-    );
-    for output in outputs.iter() {
-        let mut issues_count = 0;
-        let mut reviews_count = 0;
-        let mut commits_count = 0;
-
-        for contribution in output.contributions.iter() {
-            match contribution.contribution {
-                GithubContribution::Issue(_) => issues_count += 1,
-                GithubContribution::Review(_) => reviews_count += 1,
-                GithubContribution::Commit(_) => commits_count += 1,
-            }
+    if let Some(path) = args.output.as_ref() {
+        let records = export::flatten(&outputs);
+        let file = std::fs::File::create(path)?;
+        match args.format {
+            ExportFormat::Csv => export::write_csv(&records, file)?,
+            ExportFormat::Json => export::write_json(&records, file)?,
         }
-        println!(
-            "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10}",
-            output
-                .user
-                .as_ref()
-                .map(|u| u.inner.login.as_str())
-                .unwrap_or("None"),
-            issues_count,
-            reviews_count,
-            commits_count,
-            output.contributions.len(),
-        );
-    }
-    println!(
-        "Total Contributions: {}",
-        outputs
-            .iter()
-            .fold(0, |sum, output| sum + output.contributions.len())
-    );
-
-    let mut per_repo: HashMap<Repo, Vec<Contribution>> = HashMap::new();
-    for contribution in outputs
-        .iter()
-        .flat_map(|output| output.contributions.clone())
-    {
-        let value = per_repo
-            .entry(contribution.repo.clone())
-            .or_insert(Vec::new());
-        (*value).push(contribution.clone());
     }
 
-    println!("--");
-
-    println!(
-        "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10}",
-        "repo", "issues", "reviews", "commits", "all"
-    );
-    for (repo, contributions) in per_repo.iter() {
-        let mut issues_count = 0;
-        let mut reviews_count = 0;
-        let mut commits_count = 0;
-
-        for contribution in contributions.iter() {
-            match contribution.contribution {
-                GithubContribution::Issue(_) => issues_count += 1,
-                GithubContribution::Review(_) => reviews_count += 1,
-                GithubContribution::Commit(_) => commits_count += 1,
-            }
-        }
-        println!(
-            "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10}",
-            format!("{}/{}", repo.org, repo.name),
-            issues_count,
-            reviews_count,
-            commits_count,
-            contributions.len(),
-        );
+    match args.report_format {
+        ReportFormat::Table => report::print_table(&outputs),
+        ReportFormat::Json => report::write_json(&outputs, std::io::stdout())?,
+        ReportFormat::Csv => report::write_csv(&outputs, std::io::stdout())?,
     }
-    println!(
-        "Total Contributions: {}",
-        per_repo
-            .iter()
-            .fold(0, |sum, (_, contributions)| sum + contributions.len())
-    );
 
     Ok(())
 }
@@ -1,11 +1,11 @@
 use octocrab::models::User;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::{PartialEq, PartialOrd},
     fmt,
 };
 
-#[derive(Clone, Debug, Deserialize, PartialEq, PartialOrd, Eq, Hash)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, PartialOrd, Eq, Hash)]
 pub struct Repo {
     pub org: String,
     pub name: String,
@@ -26,7 +26,7 @@ impl fmt::Display for Repo {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct EnrichedUser {
     #[serde(flatten)]
     pub inner: User,
@@ -45,24 +45,45 @@ impl fmt::Debug for EnrichedUser {
     }
 }
 
+pub mod issue_event {
+    use chrono::{offset::Utc, DateTime};
+    use octocrab::models::{issues::Milestone, Label, User};
+    use serde::{Deserialize, Serialize};
+
+    /// One entry from a repo's issue timeline (labeling, assigning, closing,
+    /// review requests, etc.), not just the comments and state changes a
+    /// `Issue` itself carries.
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct IssueEvent {
+        pub id: u64,
+        pub actor: User,
+        pub event: String,
+        pub assignee: Option<User>,
+        pub label: Option<Label>,
+        pub milestone: Option<Milestone>,
+        pub requested_reviewer: Option<User>,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
 pub mod commit {
     use chrono::{offset::Utc, DateTime};
     use octocrab::models::repos::Commit;
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
 
-    #[derive(Clone, Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct EnrichedCommit {
         #[serde(flatten)]
         pub inner: Commit,
         pub commit: CommitObject,
     }
 
-    #[derive(Clone, Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct CommitObject {
         pub author: Author,
     }
 
-    #[derive(Clone, Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct Author {
         pub name: String,
         pub email: String,
@@ -0,0 +1,253 @@
+//! Render the aggregated per-user and per-repo contribution counts -- and,
+//! for `ReportFormat::Json`, the full `Output` list they're drawn from -- in
+//! a format downstream pipelines can consume instead of only the
+//! human-readable table `main` used to print unconditionally.
+use crate::{contribution::GithubContribution, github_contribution_collector::Output, models::Repo};
+use serde::Serialize;
+use std::{collections::HashMap, io::Write};
+
+/// Contribution format to print the per-user/per-repo report in.
+#[derive(Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            other => Err(format!(
+                "unknown report format `{}`, expected table, json, or csv",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Counts {
+    pub issues: usize,
+    pub reviews: usize,
+    pub commits: usize,
+    pub events: usize,
+}
+
+impl Counts {
+    pub fn total(&self) -> usize {
+        self.issues + self.reviews + self.commits + self.events
+    }
+
+    fn add(&mut self, contribution: &GithubContribution) {
+        match contribution {
+            GithubContribution::Issue(_) => self.issues += 1,
+            GithubContribution::Review(_) => self.reviews += 1,
+            GithubContribution::Commit(_) => self.commits += 1,
+            GithubContribution::IssueEvent(_) => self.events += 1,
+        }
+    }
+}
+
+// `Counts`' fields are inlined directly rather than nested behind
+// `#[serde(flatten)]`: flattening forces serde to serialize via
+// `serialize_map`, which the `csv` crate's writer doesn't support, so
+// `write_csv` would fail at runtime on every row.
+#[derive(Debug, Serialize)]
+pub struct UserReport {
+    pub handle: String,
+    pub issues: usize,
+    pub reviews: usize,
+    pub commits: usize,
+    pub events: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepoReport {
+    pub repo: String,
+    pub issues: usize,
+    pub reviews: usize,
+    pub commits: usize,
+    pub events: usize,
+    pub total: usize,
+}
+
+/// One row per `Output`, its contributions counted by type.
+pub fn user_reports(outputs: &[Output]) -> Vec<UserReport> {
+    outputs
+        .iter()
+        .map(|output| {
+            let mut counts = Counts::default();
+            for contribution in &output.contributions {
+                counts.add(&contribution.contribution);
+            }
+            let total = counts.total();
+
+            UserReport {
+                handle: output
+                    .user
+                    .as_ref()
+                    .map(|user| user.inner.login.clone())
+                    .unwrap_or_else(|| "None".to_string()),
+                issues: counts.issues,
+                reviews: counts.reviews,
+                commits: counts.commits,
+                events: counts.events,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// One row per repo, aggregating every output's contributions to it.
+pub fn repo_reports(outputs: &[Output]) -> Vec<RepoReport> {
+    let mut by_repo: HashMap<Repo, Counts> = HashMap::new();
+    for output in outputs {
+        for contribution in &output.contributions {
+            by_repo
+                .entry(contribution.repo.clone())
+                .or_default()
+                .add(&contribution.contribution);
+        }
+    }
+
+    by_repo
+        .into_iter()
+        .map(|(repo, counts)| {
+            let total = counts.total();
+            RepoReport {
+                repo: repo.to_string(),
+                issues: counts.issues,
+                reviews: counts.reviews,
+                commits: counts.commits,
+                events: counts.events,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// The original fixed-width terminal tables: one row per user, one row per
+/// repo, each followed by a grand total.
+pub fn print_table(outputs: &[Output]) {
+    println!(
+        "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10} {5: <10}",
+        "handle", "issues", "reviews", "commits", "events", "all"
+    );
+    let users = user_reports(outputs);
+    for user in &users {
+        println!(
+            "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10} {5: <10}",
+            user.handle,
+            user.issues,
+            user.reviews,
+            user.commits,
+            user.events,
+            user.total,
+        );
+    }
+    println!(
+        "Total Contributions: {}",
+        users.iter().fold(0, |sum, user| sum + user.total)
+    );
+
+    println!("--");
+
+    println!(
+        "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10} {5: <10}",
+        "repo", "issues", "reviews", "commits", "events", "all"
+    );
+    let repos = repo_reports(outputs);
+    for repo in &repos {
+        println!(
+            "{0: <40} {1: <10} {2: <10} {3: <10} {4: <10} {5: <10}",
+            repo.repo,
+            repo.issues,
+            repo.reviews,
+            repo.commits,
+            repo.events,
+            repo.total,
+        );
+    }
+    println!(
+        "Total Contributions: {}",
+        repos.iter().fold(0, |sum, repo| sum + repo.total)
+    );
+}
+
+/// Serialize the full `Output` list (handle, per-kind counts are
+/// recoverable from `contributions`, and the repo breakdown is implicit in
+/// each contribution's `repo`) as a single JSON array.
+pub fn write_json(outputs: &[Output], writer: impl Write) -> serde_json::Result<()> {
+    serde_json::to_writer_pretty(writer, outputs)
+}
+
+/// Write the per-user and per-repo aggregate counts as two CSV tables,
+/// separated by a `--` line.
+pub fn write_csv(outputs: &[Output], mut writer: impl Write) -> csv::Result<()> {
+    {
+        let mut csv_writer = csv::Writer::from_writer(&mut writer);
+        for user in user_reports(outputs) {
+            csv_writer.serialize(&user)?;
+        }
+        csv_writer.flush()?;
+    }
+
+    writeln!(writer, "--")?;
+
+    {
+        let mut csv_writer = csv::Writer::from_writer(&mut writer);
+        for repo in repo_reports(outputs) {
+            csv_writer.serialize(&repo)?;
+        }
+        csv_writer.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_report_serializes_to_csv_without_flattening_counts() {
+        let user = UserReport {
+            handle: "octocat".to_string(),
+            issues: 1,
+            reviews: 2,
+            commits: 3,
+            events: 4,
+            total: 10,
+        };
+
+        let mut csv_writer = csv::Writer::from_writer(vec![]);
+        csv_writer.serialize(&user).unwrap();
+        let output = String::from_utf8(csv_writer.into_inner().unwrap()).unwrap();
+
+        assert_eq!(output, "handle,issues,reviews,commits,events,total\noctocat,1,2,3,4,10\n");
+    }
+
+    #[test]
+    fn repo_report_serializes_to_csv_without_flattening_counts() {
+        let repo = RepoReport {
+            repo: "org/repo".to_string(),
+            issues: 1,
+            reviews: 0,
+            commits: 5,
+            events: 0,
+            total: 6,
+        };
+
+        let mut csv_writer = csv::Writer::from_writer(vec![]);
+        csv_writer.serialize(&repo).unwrap();
+        let output = String::from_utf8(csv_writer.into_inner().unwrap()).unwrap();
+
+        assert_eq!(output, "repo,issues,reviews,commits,events,total\norg/repo,1,0,5,0,6\n");
+    }
+}